@@ -0,0 +1,94 @@
+//! Benchmarks of each day's parse and solve stages on the real inputs, and of alternative strategies.
+//!
+//! Run a subset with e.g. `cargo bench --bench days -- day9`. Days whose input is missing are skipped.
+
+use std::{fs, hint::black_box, time::Duration};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use advent::{answers::KnownAnswers, day1, day2, day3, day4, day5, day6, day7, day8, day9, day10, day11, day12, Solution};
+
+fn read_input(day: u8, test: bool) -> Option<Vec<u8>> {
+    let path = advent::input_path(day, test);
+    match fs::read(&path) {
+        Ok(input) => Some(input),
+        Err(e) => {
+            eprintln!("Skipping day {day}: {}: {e}", path.display());
+            None
+        },
+    }
+}
+
+/// Benchmark parsing and both parts of `solution` as `{name}/parse`, `{name}/part1` and `{name}/part2`
+fn bench_stages<S: Solution>(c: &mut Criterion, name: &str, solution: &S, input: &[u8]) {
+    let mut group = c.benchmark_group(name);
+    group.sample_size(20).measurement_time(Duration::from_secs(5));
+
+    group.bench_function("parse", |b| b.iter(|| solution.parse(black_box(input))));
+
+    let parsed = match solution.parse(input) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("Skipping {name}: {e}");
+            return;
+        },
+    };
+    group.bench_function("part1", |b| b.iter(|| solution.part1(black_box(&parsed))));
+    group.bench_function("part2", |b| b.iter(|| solution.part2(black_box(&parsed))));
+    group.finish();
+}
+
+/// Benchmark `solution` on the real input of its day, with the input's parameters from answers.toml
+fn bench_day<S: Solution>(c: &mut Criterion, mut solution: S) {
+    let name = format!("day{}", S::DAY);
+    let known = KnownAnswers::load_default().ok();
+    if let Err(e) = solution.configure(known.as_ref().and_then(|known| known.params(&name)).unwrap_or(&Default::default())) {
+        eprintln!("Skipping {name}: {e}");
+        return;
+    }
+    if let Some(input) = read_input(S::DAY, false) {
+        bench_stages(c, &name, &solution, &input);
+    }
+}
+
+fn days(c: &mut Criterion) {
+    bench_day(c, day1::Day1);
+    bench_day(c, day2::Day2);
+    bench_day(c, day3::Day3);
+    bench_day(c, day4::Day4);
+    bench_day(c, day5::Day5);
+    bench_day(c, day6::Day6);
+    bench_day(c, day7::Day7);
+    bench_day(c, day8::Day8::default());
+    bench_day(c, day9::Day9::default());
+    bench_day(c, day10::Day10::default());
+    bench_day(c, day11::Day11);
+    bench_day(c, day12::Day12);
+}
+
+/// Alternative strategies, compared on the same input
+fn strategies(c: &mut Criterion) {
+    // the uncompressed grid of the real input doesn't fit in memory, so compare on the example
+    if let Some(input) = read_input(9, true) {
+        let mut group = c.benchmark_group("day9_test/part2");
+        for (name, compress) in [("compressed", true), ("uncompressed", false)] {
+            let solution = day9::Day9 { compress, ..Default::default() };
+            let tiles = solution.parse(input.as_slice()).expect("Failed to parse day 9 example");
+            group.bench_function(name, |b| b.iter(|| solution.part2(black_box(&tiles))));
+        }
+        group.finish();
+    }
+
+    if let Some(input) = read_input(10, false) {
+        let mut group = c.benchmark_group("day10/part1");
+        let machines = day10::Day10::default().parse(input.as_slice()).expect("Failed to parse day 10 input");
+        for (name, light_solver) in [("elimination", day10::LightSolver::Elimination), ("bfs", day10::LightSolver::Bfs)] {
+            let solution = day10::Day10 { light_solver, ..Default::default() };
+            group.bench_function(name, |b| b.iter(|| solution.part1(black_box(&machines))));
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, days, strategies);
+criterion_main!(benches);
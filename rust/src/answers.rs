@@ -0,0 +1,94 @@
+use std::{fs, path::{Path, PathBuf}};
+
+use toml::{Table, Value};
+
+use super::{input_dir, Answer, Answers, Error, Result};
+
+/// Known answers and input parameters, stored in `answers.toml` in the input directory.
+///
+/// Each table is named after an input file (without extension), with optional `part1` and `part2` keys.
+/// Any other keys are parameters of the input, passed to [`Solution::configure`](crate::Solution::configure):
+/// ```toml
+/// [day8_test]
+/// part1 = 40
+/// part2 = 25272
+/// connections = 10
+/// ```
+pub struct KnownAnswers {
+    table: Table,
+}
+
+/// Expected answers for a single input. Parts without a known answer are `None`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Expected {
+    pub part1: Option<Answer>,
+    pub part2: Option<Answer>,
+}
+
+/// Result of comparing an answer against the expected value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Check {
+    Correct,
+    Incorrect { expected: Answer },
+    Unknown,
+}
+
+pub fn answers_path() -> PathBuf {
+    input_dir().join("answers.toml")
+}
+
+impl KnownAnswers {
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Load `answers.toml` from the input directory
+    pub fn load_default() -> Result<Self> {
+        Self::load(&answers_path())
+    }
+
+    pub fn parse(s: &str) -> Result<Self> {
+        let table = s.parse::<Table>().map_err(|e| {
+            let err = Error::parse(e.message(), "");
+            match e.span() {
+                Some(span) => err.at_line(s[..span.start].lines().count().max(1)),
+                None => err,
+            }
+        })?;
+        Ok(Self { table })
+    }
+
+    /// All keys for the input named `name`, including its parameters
+    pub fn params(&self, name: &str) -> Option<&Table> {
+        self.table.get(name).and_then(Value::as_table)
+    }
+
+    /// Expected answers for the input named `name`
+    pub fn get(&self, name: &str) -> Result<Expected> {
+        let Some(entry) = self.table.get(name) else { return Ok(Expected::default()) };
+        let entry = entry.as_table().ok_or_else(|| Error::parse("Expected a table of answers", name))?;
+
+        let part = |key: &str| -> Result<Option<Answer>> {
+            match entry.get(key) {
+                None => Ok(None),
+                Some(&Value::Integer(v)) => Ok(Some(Answer::Signed(v))),
+                Some(v) => Err(Error::parse(format!("Invalid answer for [{name}] {key}"), v.to_string())),
+            }
+        };
+        Ok(Expected { part1: part("part1")?, part2: part("part2")? })
+    }
+}
+
+impl Expected {
+    pub fn check(&self, answers: &Answers) -> [Check; 2] {
+        [check(self.part1, answers.part1), check(self.part2, answers.part2)]
+    }
+}
+
+pub fn check(expected: Option<Answer>, actual: Answer) -> Check {
+    match expected {
+        None => Check::Unknown,
+        Some(expected) if expected == actual => Check::Correct,
+        Some(expected) => Check::Incorrect { expected },
+    }
+}
@@ -1,90 +1,267 @@
-use clap::{Parser, Subcommand};
+use std::{io, ops::RangeInclusive, path::PathBuf, process::ExitCode, time::Duration};
+
+use clap::{error::ErrorKind, ArgMatches, Command, CommandFactory, FromArgMatches, Parser, Subcommand};
+
+use toml::Table;
+
+use advent::{answers::{Check, Expected, KnownAnswers}, Day, Error, InputSource, DAYS};
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 #[command(propagate_version = true)]
+#[command(after_help = "Inputs are read from dayN.txt in $ADVENT_INPUT_DIR (default: input/ in the source checkout)")]
 struct Args {
     /// Increase verbosity
     #[arg(short, long, action = clap::ArgAction::Count, global = true)]
     verbose: u8,
 
-    /// Problem to run
-    #[command(subcommand)]
-    problem: Problem,
+    /// Run on the example input
+    #[arg(long, global = true)]
+    test: bool,
+
+    /// Read input from PATH instead of the input directory (`-` for stdin)
+    #[arg(short, long, value_name = "PATH", global = true, conflicts_with = "test")]
+    input: Option<PathBuf>,
+
+    /// Compare answers against answers.toml in the input directory, failing on mismatch
+    #[arg(long, global = true)]
+    check: bool,
 }
 
+/// Commands running several days, in addition to the `dayN` commands
 #[derive(Subcommand)]
-enum Problem {
-    Day1 {
-        #[arg(long)]
-        test: bool,
-    },
-    Day2 { },
-    Day3 {
-        #[arg(long)]
-        test: bool,
-    },
-    Day4 {
-        #[arg(long)]
-        test: bool,
-    },
-    Day5 {
-        #[arg(long)]
-        test: bool,
+enum Batch {
+    /// Run every day and print a summary table
+    All {
+        #[command(flatten)]
+        options: BatchOptions,
     },
-    Day6 {
-        #[arg(long)]
-        test: bool,
-    },
-    Day7 {
-        #[arg(long)]
-        test: bool,
-    },
-    Day8 {
-        #[arg(long)]
-        test: bool,
-    },
-    Day9 {
-        #[arg(long)]
-        test: bool,
-        #[arg(long = "no-compress", action = clap::ArgAction::SetFalse)]
-        compress: bool,
-    },
-    Day10 {
-        #[arg(long)]
-        test: bool,
-    },
-    Day11 {
-        #[arg(long)]
-        test: bool,
-    },
-    Day12 {
-        #[arg(long)]
-        test: bool,
+    /// Run a range of days (e.g. `3..=9`) and print a summary table
+    Range {
+        #[arg(value_parser = parse_day_range)]
+        days: RangeInclusive<u8>,
+        #[command(flatten)]
+        options: BatchOptions,
     },
 }
 
-impl Problem {
-    fn run(&self) -> Result<(), String> {
-        match self {
-            Self::Day1 { test } => { advent::day1::run(*test) },
-            Self::Day2 { } => { advent::day2::run() },
-            Self::Day3 { test } => { advent::day3::run(*test) },
-            Self::Day4 { test } => { advent::day4::run(*test) },
-            Self::Day5 { test } => { advent::day5::run(*test) },
-            Self::Day6 { test } => { advent::day6::run(*test) },
-            Self::Day7 { test } => { advent::day7::run(*test) },
-            Self::Day8 { test } => { advent::day8::run(*test) },
-            Self::Day9 { test, compress } => { advent::day9::run(*test, *compress) },
-            Self::Day10 { test } => { advent::day10::run(*test) },
-            Self::Day11 { test } => { advent::day11::run(*test) },
-            Self::Day12 { test } => { advent::day12::run(*test) },
+#[derive(clap::Args)]
+struct BatchOptions {
+    /// Continue past failing days, and report failures at the end
+    #[arg(short, long)]
+    keep_going: bool,
+}
+
+fn parse_day_range(s: &str) -> Result<RangeInclusive<u8>, String> {
+    let parse = |v: &str| v.trim().parse::<u8>().map_err(|_| format!("Invalid day '{v}'"));
+
+    let days = if let Some((start, end)) = s.split_once("..=") {
+        parse(start)?..=parse(end)?
+    } else if let Some((start, end)) = s.split_once("..") {
+        let start = parse(start)?;
+        if end.trim().is_empty() {
+            start..=u8::MAX
+        } else {
+            let end = parse(end)?;
+            if end <= start { return Err(format!("Empty range '{s}'")); }
+            start..=end - 1
         }
-    }
+    } else {
+        let day = parse(s)?;
+        day..=day
+    };
+    if days.is_empty() { return Err(format!("Empty range '{s}'")); }
+    if !DAYS.iter().any(|day| days.contains(&day.day)) { return Err(format!("No days in range '{s}'")); }
+    Ok(days)
+}
+
+fn cli() -> Command {
+    DAYS.iter().fold(
+        Batch::augment_subcommands(Args::command()).subcommand_required(true),
+        |command, day| command.subcommand(day.command())
+    )
 }
 
-fn main() -> Result<(), String> {
-    let cli = Args::parse();
+fn main() -> ExitCode {
+    let matches = cli().get_matches();
+    let cli = match Args::from_arg_matches(&matches) {
+        Ok(cli) => cli,
+        Err(e) => e.exit(),
+    };
     advent::set_verbosity(cli.verbose);
-    cli.problem.run()
-}
\ No newline at end of file
+
+    if let Ok(batch) = Batch::from_arg_matches(&matches) {
+        if cli.input.is_some() {
+            Args::command().error(ErrorKind::ArgumentConflict, "--input can't be used when running multiple days").exit();
+        }
+        let (days, options) = match batch {
+            Batch::All { options } => (1..=u8::MAX, options),
+            Batch::Range { days, options } => (days, options),
+        };
+        let known = load_known(cli.check);
+        return run_batch(DAYS.iter().filter(|day| days.contains(&day.day)), cli.test, known.as_ref(), cli.check, &options);
+    }
+
+    // subcommand is required, so this must be a day
+    let (name, day_matches) = matches.subcommand().unwrap();
+    let day = DAYS.iter().find(|day| day.name() == name).unwrap();
+
+    let source = match &cli.input {
+        Some(path) => InputSource::from_arg(path),
+        None => InputSource::File(advent::input_path(day.day, cli.test)),
+    };
+    let known = load_known(cli.check);
+    let params = input_params(known.as_ref(), &source);
+    let result = source.open().and_then(|mut input| day.run(day_matches, &params, &mut input));
+
+    match result {
+        Ok(answers) => {
+            let Some(known) = known.filter(|_| cli.check) else {
+                println!("Part 1: {}", answers.part1);
+                println!("Part 2: {}", answers.part2);
+                return ExitCode::SUCCESS;
+            };
+
+            let expected = expected_answers(&known, &source);
+            if expected == Expected::default() {
+                eprintln!("Warning: no known answers for {source}");
+            }
+            let checks = expected.check(&answers);
+            for (part, (answer, check)) in [answers.part1, answers.part2].into_iter().zip(checks).enumerate() {
+                println!("Part {}: {answer} ({})", part + 1, format_check(check));
+            }
+            if checks.iter().any(|check| matches!(check, Check::Incorrect { .. })) {
+                ExitCode::FAILURE
+            } else {
+                ExitCode::SUCCESS
+            }
+        },
+        Err(e) => {
+            eprintln!("Error: {source}: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Load answers.toml, which may only be missing if the answers aren't being checked
+fn load_known(required: bool) -> Option<KnownAnswers> {
+    match KnownAnswers::load_default() {
+        Ok(known) => Some(known),
+        Err(Error::Io(e)) if !required && e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => {
+            eprintln!("Error: {}: {e}", advent::answers::answers_path().display());
+            std::process::exit(1);
+        }
+    }
+}
+
+fn expected_answers(known: &KnownAnswers, source: &InputSource) -> Expected {
+    let Some(name) = source.name() else { return Expected::default() };
+    match known.get(name) {
+        Ok(expected) => expected,
+        Err(e) => {
+            eprintln!("Error: {}: {e}", advent::answers::answers_path().display());
+            std::process::exit(1);
+        }
+    }
+}
+
+fn input_params(known: Option<&KnownAnswers>, source: &InputSource) -> Table {
+    known.zip(source.name()).and_then(|(known, name)| known.params(name)).cloned().unwrap_or_default()
+}
+
+fn format_check(check: Check) -> String {
+    match check {
+        Check::Correct => "correct".to_owned(),
+        Check::Incorrect { expected } => format!("WRONG, expected {expected}"),
+        Check::Unknown => "unknown".to_owned(),
+    }
+}
+
+/// Default options for `day`, as if it was run on its own
+fn day_matches(day: &Day, test: bool) -> ArgMatches {
+    let name = day.name();
+    let args: &[&str] = if test { &["advent", "--test", &name] } else { &["advent", &name] };
+    cli().get_matches_from(args).remove_subcommand().expect("missing day subcommand").1
+}
+
+fn format_time(time: Duration) -> String {
+    format!("{:.3} ms", time.as_secs_f64() * 1e3)
+}
+
+fn run_batch<'a>(
+    days: impl Iterator<Item = &'a Day>, test: bool, known: Option<&KnownAnswers>, check_answers: bool, options: &BatchOptions
+) -> ExitCode {
+    // day, part, answer, time, check
+    let mut rows: Vec<[String; 5]> = Vec::new();
+    let mut failures: Vec<(String, Error)> = Vec::new();
+    let mut mismatches: Vec<String> = Vec::new();
+    let mut total = Duration::ZERO;
+
+    for day in days {
+        let source = InputSource::File(advent::input_path(day.day, test));
+        let params = input_params(known, &source);
+        let report = match source.open().and_then(|mut input| day.run_timed(&day_matches(day, test), &params, &mut input)) {
+            Ok(report) => report,
+            Err(e) => {
+                rows.push([day.day.to_string(), "parse".to_owned(), "FAILED".to_owned(), String::new(), String::new()]);
+                failures.push((format!("day {} ({source})", day.day), e));
+                if options.keep_going { continue } else { break }
+            },
+        };
+        total += report.parse_time + report.part1_time + report.part2_time;
+        let expected = known.filter(|_| check_answers).map(|known| expected_answers(known, &source)).unwrap_or_default();
+
+        rows.push([day.day.to_string(), "parse".to_owned(), String::new(), format_time(report.parse_time), String::new()]);
+        let parts = [
+            (1, report.part1, report.part1_time, expected.part1),
+            (2, report.part2, report.part2_time, expected.part2),
+        ];
+        for (part, result, time, expected) in parts {
+            let (answer, check) = match result {
+                Ok(answer) => {
+                    let check = advent::answers::check(expected, answer);
+                    if let Check::Incorrect { expected } = check {
+                        mismatches.push(format!("day {} part {part}: got {answer}, expected {expected}", day.day));
+                    }
+                    (answer.to_string(), if check_answers { format_check(check) } else { String::new() })
+                },
+                Err(e) => {
+                    failures.push((format!("day {} part {part}", day.day), e));
+                    ("FAILED".to_owned(), String::new())
+                }
+            };
+            rows.push([day.day.to_string(), part.to_string(), answer, format_time(time), check]);
+        }
+        if !(failures.is_empty() && mismatches.is_empty()) && !options.keep_going { break }
+    }
+    rows.push(["total".to_owned(), String::new(), String::new(), format_time(total), String::new()]);
+
+    let header = ["Day", "Part", "Answer", "Time", if check_answers { "Check" } else { "" }].map(str::to_owned);
+    let widths: Vec<usize> = (0..5).map(|i| {
+        rows.iter().chain([&header]).map(|row| row[i].len()).max().unwrap_or(0)
+    }).collect();
+
+    for row in [&header].into_iter().chain(&rows) {
+        let line = format!("{:>w0$}  {:>w1$}  {:>w2$}  {:>w3$}  {}", row[0], row[1], row[2], row[3], row[4],
+            w0 = widths[0], w1 = widths[1], w2 = widths[2], w3 = widths[3]);
+        println!("{}", line.trim_end());
+    }
+
+    if failures.is_empty() && mismatches.is_empty() {
+        return ExitCode::SUCCESS;
+    }
+    if !failures.is_empty() {
+        eprintln!("\n{} failure(s):", failures.len());
+        for (what, e) in failures {
+            eprintln!("  {what}: {e}");
+        }
+    }
+    if !mismatches.is_empty() {
+        eprintln!("\n{} wrong answer(s):", mismatches.len());
+        for mismatch in mismatches {
+            eprintln!("  {mismatch}");
+        }
+    }
+    ExitCode::FAILURE
+}
@@ -1,11 +1,11 @@
-use core::fmt;
-use std::{fs::File, io::{BufRead, BufReader}};
+use std::io::BufRead;
 
-use super::{verbosity, input_dir};
+use super::{verbosity, Error, Result, Solution};
 
 static DIAL_SIZE: i64 = 100;
+static START_POS: i64 = 50;
 
-fn try_parse_line<'a>(line: &'a str) -> Option<(i64, i64)> {
+fn try_parse_line(line: &str) -> Option<(i64, i64)> {
     if line.len() < 2 { return None; }
     Some((
         match line.chars().next().unwrap().to_ascii_uppercase() {
@@ -13,22 +13,23 @@ fn try_parse_line<'a>(line: &'a str) -> Option<(i64, i64)> {
             'R' =>  1,
             _ => return None,
         },
-        (&line[1..]).parse().ok()?,
+        line[1..].parse().ok()?,
     ))
 }
 
-pub fn process<I, E>(lines: I, start_pos: i64, verbosity: u8) -> Result<(i64, i64), String>
-where I: IntoIterator<Item = Result<String, E>>,
-      E: fmt::Display
-{
+pub fn parse_moves<R: BufRead>(input: R) -> Result<Vec<(i64, i64)>> {
+    input.lines().enumerate().map(|(i, line)| {
+        let line = line?;
+        try_parse_line(&line).ok_or_else(|| Error::parse("Invalid rotation", line).at_line(i + 1))
+    }).collect()
+}
+
+pub fn process(moves: &[(i64, i64)], start_pos: i64, verbosity: u8) -> (i64, i64) {
     let mut pos = start_pos;
     let mut stop_count = 0;
     let mut pass_count = 0;
 
-    for line in lines.into_iter() {
-        let line = line.map_err(|e| format!("Error reading file: {}", e))?;
-        let (sign, value) = try_parse_line(&line).ok_or_else(|| format!("Invalid line {:?}", line))?;
-
+    for &(sign, value) in moves {
         let offset = if sign * pos < 0 { DIAL_SIZE - pos } else { pos };
 
         pass_count += (offset + value) / DIAL_SIZE;
@@ -38,27 +39,34 @@ where I: IntoIterator<Item = Result<String, E>>,
         if pos == 0 { stop_count += 1 }
 
         if verbosity > 0 {
+            let line = format!("{}{}", if sign < 0 { 'L' } else { 'R' }, value);
             println!("{:<4} pos: {:>2}, passes: {:>4}, stops: {:>4}", line, pos, pass_count, stop_count);
         }
     }
 
-    Ok((stop_count, pass_count))
+    (stop_count, pass_count)
 }
 
 
-pub fn run(test: bool) -> Result<(), String> {
-    let verbosity = verbosity();
+#[derive(Debug, Clone, clap::Args)]
+pub struct Day1;
 
-    let mut input_path = input_dir().to_owned();
-    input_path.push(if test { "day1_test.txt" } else { "day1.txt" });
+impl Solution for Day1 {
+    const DAY: u8 = 1;
 
-    println!("input_path: {:?}", input_path);
+    type Input = Vec<(i64, i64)>;
+    type Part1 = i64;
+    type Part2 = i64;
 
-    let file = File::open(input_path).expect("Failed to open input file");
-    let (stop_count, pass_count) = process(BufReader::new(file).lines(), 50, verbosity)?;
+    fn parse<R: BufRead>(&self, input: R) -> Result<Self::Input> {
+        parse_moves(input)
+    }
 
-    println!("Stopped at 0 {} times", stop_count);
-    println!("Passed 0 {} times", pass_count);
+    fn part1(&self, input: &Self::Input) -> Result<i64> {
+        Ok(process(input, START_POS, verbosity()).0)
+    }
 
-    Ok(())
-}
\ No newline at end of file
+    fn part2(&self, input: &Self::Input) -> Result<i64> {
+        Ok(process(input, START_POS, 0).1)
+    }
+}
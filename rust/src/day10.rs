@@ -1,15 +1,16 @@
-use std::{collections::{HashSet, VecDeque}, fmt, fs::File, hash::Hash, io::{BufRead, BufReader}, str::FromStr, sync::OnceLock};
+use std::{collections::HashSet, fmt, hash::Hash, io::BufRead, str::FromStr, sync::OnceLock};
 
 use itertools::Itertools;
 use regex::Regex;
+#[cfg(feature = "highs")]
 use highs::{ColProblem, HighsModelStatus, Sense};
 
-use super::{input_dir, verbosity};
+use super::{gf2, ilp, verbosity, Error, Result, Solution};
 
 static MACHINE_RE: OnceLock<Regex> = OnceLock::new();
 static BUTTONS_RE: OnceLock<Regex> = OnceLock::new();
 
-struct MachineSpec {
+pub struct MachineSpec {
     lights: Vec<bool>,
     buttons: Vec<Vec<usize>>,
     voltages: Vec<u64>,
@@ -22,9 +23,9 @@ impl MachineSpec {
 }
 
 impl FromStr for MachineSpec {
-    type Err = String;
+    type Err = Error;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    fn from_str(s: &str) -> Result<Self> {
         let re = MACHINE_RE.get_or_init(|| Regex::new(
             r"^\[(?<lights>[.#]+)\](?<buttons>(?: \(\d+(?:,\d+)*\))+) \{(?<voltages>[\d,]+)\}$"
         ).expect("Invalid machine regex"));
@@ -33,7 +34,7 @@ impl FromStr for MachineSpec {
             r"\((\d+(?:,\d+)*)\)"
         ).expect("Invalid buttons regex"));
 
-        re.captures(s.trim()).and_then(|caps| {
+        let spec = re.captures(s.trim()).and_then(|caps| {
             let lights = caps["lights"].chars().map(|c| c == '#').collect();
             let voltages = caps["voltages"].split(',').map(|v| v.trim().parse::<u64>().ok()).collect::<Option<_>>()?;
             let buttons = buttons_re.captures_iter(&caps["buttons"]).map(|caps| {
@@ -43,7 +44,15 @@ impl FromStr for MachineSpec {
             Some(Self {
                 lights, buttons, voltages
             })
-        }).ok_or_else(|| format!("Invalid machine specification: {s}"))
+        }).ok_or_else(|| Error::parse("Invalid machine specification", s))?;
+
+        if spec.lights.len() != spec.voltages.len() {
+            return Err(Error::parse(format!("{} lights but {} voltages", spec.lights.len(), spec.voltages.len()), s));
+        }
+        if let Some(&idx) = spec.buttons.iter().flatten().find(|&&idx| idx >= spec.lights.len()) {
+            return Err(Error::parse(format!("Button wired to nonexistent light {idx}"), s));
+        }
+        Ok(spec)
     }
 }
 
@@ -57,109 +66,475 @@ impl fmt::Display for MachineSpec {
     }
 }
 
-#[derive(Clone)]
-struct MachinePart1<'a> {
-    spec: &'a MachineSpec,
-    lights: Vec<bool>,
-    moves: usize,
+/// Light states with one bit per light, where pressing a button XORs in the lights it's wired to
+trait LightBits: Clone + Eq + Hash {
+    fn from_lights(lights: impl IntoIterator<Item = usize>, len: usize) -> Self;
+
+    fn press(&self, button: &Self) -> Self;
+
+    /// Position in a dense bitmap of states, for few enough lights
+    fn index(&self) -> Option<usize> { None }
 }
 
-impl<'a> MachinePart1<'a> {
-    pub fn new(spec: &'a MachineSpec) -> Self {
+impl LightBits for u64 {
+    fn from_lights(lights: impl IntoIterator<Item = usize>, _: usize) -> Self {
+        lights.into_iter().fold(0, |bits, i| bits | 1 << i)
+    }
+
+    fn press(&self, button: &Self) -> Self { self ^ button }
+
+    fn index(&self) -> Option<usize> { Some(*self as usize) }
+}
+
+impl LightBits for u128 {
+    fn from_lights(lights: impl IntoIterator<Item = usize>, _: usize) -> Self {
+        lights.into_iter().fold(0, |bits, i| bits | 1 << i)
+    }
+
+    fn press(&self, button: &Self) -> Self { self ^ button }
+}
+
+/// Fallback for machines with more than 128 lights
+impl LightBits for Vec<u64> {
+    fn from_lights(lights: impl IntoIterator<Item = usize>, len: usize) -> Self {
+        let mut bits = vec![0; len.div_ceil(64)];
+        for i in lights { bits[i / 64] |= 1 << (i % 64); }
+        bits
+    }
+
+    fn press(&self, button: &Self) -> Self {
+        self.iter().zip(button).map(|(l, r)| l ^ r).collect()
+    }
+}
+
+/// Most lights to track visited states in a bitmap rather than a hash set (2^24 bits is 2MiB)
+const DENSE_LIGHTS: usize = 24;
+
+enum Visited<S> {
+    Dense(Vec<u64>),
+    Sparse(HashSet<S>),
+}
+
+impl<S: LightBits> Visited<S> {
+    fn new(lights: usize, start: &S) -> Self {
+        if lights <= DENSE_LIGHTS && start.index().is_some() {
+            Visited::Dense(vec![0; (1_usize << lights).div_ceil(64)])
+        } else {
+            Visited::Sparse(HashSet::new())
+        }
+    }
+
+    /// Mark `state` as visited, returning whether it's new
+    fn insert(&mut self, state: &S) -> bool {
+        match self {
+            Visited::Dense(bitmap) => {
+                let i = state.index().expect("Dense states need an index");
+                let new = bitmap[i / 64] >> (i % 64) & 1 == 0;
+                bitmap[i / 64] |= 1 << (i % 64);
+                new
+            },
+            Visited::Sparse(set) => set.insert(state.clone()),
+        }
+    }
+}
+
+/// Breadth-first search for the fewest presses to light up a machine, over states packed into `S`
+struct MachinePart1<S> {
+    lights: usize,
+    target: S,
+    buttons: Vec<S>,
+}
+
+impl<S: LightBits> MachinePart1<S> {
+    fn new(spec: &MachineSpec) -> Self {
+        let lights = spec.lights.len();
         Self {
-            spec, lights: vec![false; spec.lights.len()], moves: 0
+            lights,
+            target: S::from_lights(spec.lights.iter().positions(|&on| on), lights),
+            buttons: spec.buttons.iter().map(|button| S::from_lights(button.iter().copied(), lights)).collect(),
         }
     }
 
-    pub fn search(self) -> Option<Self> {
-        let mut visited: HashSet<Self> = HashSet::new();
-        let mut queue: VecDeque<Self> = VecDeque::new();
-        queue.push_back(self);
+    /// Presses of each button, which are all 0 or 1
+    fn search(&self) -> Option<Vec<u64>> {
+        let start = S::from_lights([], self.lights);
+        let mut presses = vec![0; self.buttons.len()];
+        if start == self.target { return Some(presses); }
 
-        while let Some(v) = queue.pop_front() {
-            for child in v.children() {
-                if child.solved() { return Some(child); }
-                if visited.contains(&child) { continue; }
-                visited.insert(child.clone());
-                queue.push_back(child);
+        let mut visited = Visited::new(self.lights, &start);
+        visited.insert(&start);
+        // states in the order they're reached, which is also the queue, with the index of the
+        // state they were reached from and the button pressed
+        let mut states: Vec<(S, usize, usize)> = vec![(start, 0, 0)];
+        let mut head = 0;
+        while head < states.len() {
+            for (b, button) in self.buttons.iter().enumerate() {
+                let child = states[head].0.press(button);
+                if child == self.target {
+                    presses[b] += 1;
+                    let mut node = head;
+                    while node != 0 {
+                        let (_, parent, b) = states[node];
+                        presses[b] += 1;
+                        node = parent;
+                    }
+                    return Some(presses);
+                }
+                if visited.insert(&child) {
+                    states.push((child, head, b));
+                }
             }
+            head += 1;
         }
         None
     }
+}
 
-    pub fn children<'b>(&'b self) -> impl IntoIterator<Item = Self> + 'b {
-        self.spec.buttons.iter().map(move |button| {
-            let mut new_lights = self.lights.clone();
-            for &idx in button { new_lights[idx] ^= true; }
-            Self { spec: self.spec, lights: new_lights, moves: self.moves + 1 }
-        })
+/// Which of a machine's targets some button presses reach
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Verified {
+    pub lights: bool,
+    pub voltages: bool,
+}
+
+/// Why a machine can't reach its lights or voltages
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Infeasible {
+    Lights(gf2::Inconsistent),
+    Voltages(ilp::Infeasible),
+}
+
+impl fmt::Display for Infeasible {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |lights: &mut dyn Iterator<Item = String>| lights.join(", ");
+        match self {
+            Infeasible::Lights(gf2::Inconsistent { rows }) => write!(
+                f, "every button toggles an even number of lights {}, but an odd number of them should be on",
+                list(&mut rows.iter().map(usize::to_string)),
+            ),
+            Infeasible::Voltages(ilp::Infeasible::Unreachable(i)) => write!(f, "no button raises voltage {i}"),
+            Infeasible::Voltages(ilp::Infeasible::Inconsistent(multipliers)) => {
+                // a combination like `2 v0 - v3`, which no button changes
+                let mut combination = String::new();
+                for (k, &(i, y)) in multipliers.iter().enumerate() {
+                    let sign = match (k, y < 0) { (0, false) => "", (0, true) => "-", (_, false) => " + ", (_, true) => " - " };
+                    let scale = if y.abs() == 1 { String::new() } else { format!("{} ", y.abs()) };
+                    combination += &format!("{sign}{scale}v{i}");
+                }
+                write!(f, "no button changes {combination}, but the voltages need it to be nonzero")
+            },
+            Infeasible::Voltages(ilp::Infeasible::NoIntegerSolution) => write!(f, "no whole number of presses reaches the voltages"),
+        }
     }
+}
 
-    pub fn solved(&self) -> bool { return self.lights.iter().zip(self.spec.lights.iter()).all(|(&l, &r)| l == r) }
+/// How to find the fewest presses to light up a machine
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum LightSolver {
+    /// Gaussian elimination over GF(2), then the solution with fewest presses among all of them
+    #[default]
+    Elimination,
+    /// Breadth-first search over the states of the lights
+    Bfs,
 }
 
-impl<'a, 'b> PartialEq<MachinePart1<'b>> for MachinePart1<'a> {
-    fn eq(&self, other: &MachinePart1<'b>) -> bool {
-        std::ptr::eq(self.spec as *const MachineSpec, other.spec as *const MachineSpec)
-            && self.lights == other.lights
+impl MachineSpec {
+    /// Fewest presses of each button to light up the machine (pressing a button twice undoes it).
+    /// Fails if there are too many buttons and lights for the search among the solutions.
+    pub fn solve_lights(&self) -> Result<std::result::Result<Vec<u64>, Infeasible>> {
+        let solutions = match gf2::Solutions::new(&self.buttons, &self.lights) {
+            Ok(solutions) => solutions,
+            Err(inconsistent) => return Ok(Err(Infeasible::Lights(inconsistent))),
+        };
+        let presses = solutions.min_weight().ok_or_else(|| Error::Solver(format!(
+            "{} free and {} pivot buttons are too many to find the fewest presses for machine: {self}",
+            solutions.free(), solutions.rank(),
+        )))?;
+        Ok(Ok(presses.into_iter().map(u64::from).collect()))
+    }
+
+    /// Fewest presses of each button to reach the machine's voltages, found exactly
+    pub fn solve_voltages(&self) -> std::result::Result<Vec<u64>, Infeasible> {
+        ilp::min_sum(&self.buttons, &self.voltages).map_err(Infeasible::Voltages)
     }
+
+    /// Fewest presses of each button to reach the machine's voltages, from the HiGHS MIP solver.
+    /// HiGHS doesn't explain infeasible machines, so they're explained by the exact solver.
+    #[cfg(feature = "highs")]
+    pub fn solve_voltages_highs(&self) -> Result<std::result::Result<Vec<u64>, Infeasible>> {
+        let mut problem = ColProblem::new();
+        let voltages = self.voltages.iter().map(|&voltage| problem.add_row(voltage as f64..=voltage as f64)).collect_vec();
+        for button in &self.buttons {
+            problem.add_integer_column(1.0, 0.., button.iter().map(|&i| (voltages[i], 1.0)))
+        }
+        let solution = problem.optimise(Sense::Minimise).solve();
+        if solution.status() == HighsModelStatus::Infeasible {
+            if let Err(infeasible) = self.solve_voltages() { return Ok(Err(infeasible)); }
+        }
+        if solution.status() != HighsModelStatus::Optimal {
+            return Err(Error::Solver(format!("HiGHS gave solution status {:?} for machine: {self}", solution.status())));
+        }
+        // integer columns come back as floats, so are checked against the voltages afterwards
+        Ok(Ok(solution.get_solution().columns().iter().map(|&v| v.round() as u64).collect_vec()))
+    }
+
+    /// Lights and voltages after pressing each button the given number of times, starting from
+    /// all lights off and no voltage. Each press toggles the lights on its button and adds one
+    /// to their voltages.
+    pub fn simulate(&self, presses: &[u64]) -> (Vec<bool>, Vec<u64>) {
+        assert_eq!(presses.len(), self.buttons.len(), "Expected a number of presses for each button");
+        let mut lights = vec![false; self.lights.len()];
+        let mut voltages = vec![0; self.voltages.len()];
+        for (button, &n) in self.buttons.iter().zip(presses) {
+            for &i in button {
+                lights[i] ^= n % 2 == 1;
+                voltages[i] += n;
+            }
+        }
+        (lights, voltages)
+    }
+
+    /// Whether pressing each button the given number of times reaches the machine's lights and voltages
+    pub fn verify(&self, presses: &[u64]) -> Verified {
+        let (lights, voltages) = self.simulate(presses);
+        Verified { lights: lights == self.lights, voltages: voltages == self.voltages }
+    }
+
+    /// Buttons pressed at least once, and how many times
+    pub fn describe_presses(&self, presses: &[u64]) -> String {
+        self.buttons.iter().zip(presses).filter(|&(_, &n)| n > 0)
+            .map(|(button, n)| format!("({}) x{n}", button.iter().join(",")))
+            .join(", ")
+    }
+
+    /// Fewest presses to light up the machine, by breadth-first search
+    pub fn search_lights(&self) -> std::result::Result<Vec<u64>, Infeasible> {
+        let presses = match self.lights.len() {
+            0..=64 => MachinePart1::<u64>::new(self).search(),
+            65..=128 => MachinePart1::<u128>::new(self).search(),
+            _ => MachinePart1::<Vec<u64>>::new(self).search(),
+        };
+        // the search can't say why it failed, but elimination can
+        presses.ok_or_else(|| match gf2::Solutions::new(&self.buttons, &self.lights) {
+            Ok(_) => panic!("Elimination solved a machine the search couldn't"),
+            Err(inconsistent) => Infeasible::Lights(inconsistent),
+        })
+    }
+}
+
+/// How to find the fewest presses to reach a machine's voltages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum VoltageSolver {
+    /// Branch and bound over the free variables of the integer system, in exact arithmetic
+    #[default]
+    Exact,
+    /// The HiGHS mixed integer programming solver
+    #[cfg(feature = "highs")]
+    Highs,
+}
+
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct Day10 {
+    /// How to solve the light puzzles in part 1
+    #[arg(long, value_enum, default_value_t)]
+    pub light_solver: LightSolver,
+
+    /// How to solve the voltage puzzles in part 2
+    #[arg(long, value_enum, default_value_t)]
+    pub voltage_solver: VoltageSolver,
+
+    /// List machines without a solution and total the rest, rather than failing
+    #[arg(long)]
+    pub skip_infeasible: bool,
 }
-impl<'a> Eq for MachinePart1<'a> { }
 
-impl<'a> Hash for MachinePart1<'a> {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        (self.spec as *const MachineSpec).hash(state);
-        self.lights.hash(state);
+impl Day10 {
+    /// Fail on an infeasible machine, or add it to `skipped` if skipping them
+    fn infeasible(&self, skipped: &mut Vec<(usize, Infeasible)>, i: usize, spec: &MachineSpec, reason: Infeasible) -> Result<()> {
+        if !self.skip_infeasible {
+            return Err(Error::Unsolvable(format!("Machine on line {} can't be solved, as {reason}: {spec}", i + 1)));
+        }
+        skipped.push((i, reason));
+        Ok(())
     }
 }
 
-pub fn run(test: bool) -> Result<(), String> {
-    let verbosity = verbosity();
+/// List the machines skipped by a part
+fn report_skipped(part: u8, specs: &[MachineSpec], skipped: &[(usize, Infeasible)]) {
+    if skipped.is_empty() { return }
+    println!("Part {part}: skipped {} of {} machines:", skipped.len(), specs.len());
+    for (i, reason) in skipped {
+        println!("  line {}: {}\n    {reason}", i + 1, specs[*i]);
+    }
+}
 
-    let mut input_path = input_dir().to_owned();
-    input_path.push(if test { "day10_test.txt" } else { "day10.txt" });
+impl Solution for Day10 {
+    const DAY: u8 = 10;
 
-    let file = File::open(input_path).expect("Failed to open input file");
+    type Input = Vec<MachineSpec>;
+    type Part1 = u64;
+    type Part2 = u64;
 
-    let mut specs: Vec<MachineSpec> = Vec::new();
+    fn parse<R: BufRead>(&self, input: R) -> Result<Self::Input> {
+        let mut specs: Vec<MachineSpec> = Vec::new();
 
-    for line in BufReader::new(file).lines() {
-        let line = line.map_err(|e| format!("Error reading file: {e}"))?;
-        let mut machine = MachineSpec::from_str(&line)?;
-        machine.sort_buttons();
-        specs.push(machine);
+        for (i, line) in input.lines().enumerate() {
+            let line = line?;
+            let mut machine = MachineSpec::from_str(&line).map_err(|e| e.at_line(i + 1))?;
+            machine.sort_buttons();
+            specs.push(machine);
+        }
+        Ok(specs)
     }
 
-    let mut part1_total = 0u64;
-    for spec in specs.iter() {
-        let sol = MachinePart1::new(spec).search().ok_or_else(|| format!("No solution for machine: {spec}"))?;
-        part1_total += sol.moves as u64;
-        if verbosity > 0 { println!("{}: {} moves", spec, sol.moves) };
+    fn part1(&self, specs: &Self::Input) -> Result<u64> {
+        let verbosity = verbosity();
+        let mut part1_total = 0u64;
+        let mut skipped = Vec::new();
+        for (i, spec) in specs.iter().enumerate() {
+            let presses = match self.light_solver {
+                LightSolver::Elimination => spec.solve_lights()?,
+                LightSolver::Bfs => spec.search_lights(),
+            };
+            let presses = match presses {
+                Ok(presses) => presses,
+                Err(reason) => { self.infeasible(&mut skipped, i, spec, reason)?; continue },
+            };
+            if !spec.verify(&presses).lights {
+                return Err(Error::Solver(format!("Presses {presses:?} don't light up machine: {spec}")));
+            }
+
+            let moves = presses.iter().sum::<u64>();
+            part1_total += moves;
+            if verbosity > 0 { println!("{spec}: {moves} moves: {}", spec.describe_presses(&presses)) };
+        }
+        report_skipped(1, specs, &skipped);
+        Ok(part1_total)
     }
-    println!("Part 1 total: {part1_total}\n");
 
-    let mut part2_total = 0u64;
-    for spec in specs.iter() {
-        let mut problem = ColProblem::new();
+    fn part2(&self, specs: &Self::Input) -> Result<u64> {
+        let verbosity = verbosity();
+        let mut part2_total = 0u64;
+        let mut skipped = Vec::new();
+        for (i, spec) in specs.iter().enumerate() {
+            let buttons = match self.voltage_solver {
+                VoltageSolver::Exact => spec.solve_voltages(),
+                #[cfg(feature = "highs")]
+                VoltageSolver::Highs => spec.solve_voltages_highs()?,
+            };
+            let buttons = match buttons {
+                Ok(buttons) => buttons,
+                Err(reason) => { self.infeasible(&mut skipped, i, spec, reason)?; continue },
+            };
+            if !spec.verify(&buttons).voltages {
+                return Err(Error::Solver(format!("Presses {buttons:?} don't give the voltages of machine: {spec}")));
+            }
 
-        // this is sus
-        let voltages = spec.voltages.iter().map(|&voltage| problem.add_row(voltage as f64..=voltage as f64)).collect_vec();
-        for button in &spec.buttons {
-            problem.add_integer_column(1.0, 0.., button.iter().map(|&i| (voltages[i], 1.0)))
+            let sum = buttons.iter().sum::<u64>();
+            part2_total += sum;
+
+            if verbosity > 0 {
+                println!("{spec}: {sum} moves: {}", spec.describe_presses(&buttons));
+            }
         }
-        let solution = problem.optimise(Sense::Minimise).solve(); // .map_err(|e| format!("couldn't solve: {e}"))?;
-        if solution.status() != HighsModelStatus::Optimal {
-            return Err(format!("Solver gave solution status: {:?}", solution.status()));
+        report_skipped(2, specs, &skipped);
+        Ok(part2_total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{column_sums, Rng};
+
+    #[test]
+    fn bfs_matches_elimination() {
+        let machines = [
+            "[.##.] (3) (1,3) (2) (2,3) (0,2) (0,1) {3,5,4,7}",
+            "[...#.] (0,2,3,4) (2,3) (0,4) (0,1,2) (1,2,3,4) {7,5,12,7,2}",
+            "[.###.#] (0,1,2,3,4) (0,3,4) (0,1,2,4,5) (1,2) {10,11,11,5,10,5}",
+            "[....] (0,1) (2,3) {1,1,1,1}",
+        ];
+        for machine in machines {
+            let spec = MachineSpec::from_str(machine).unwrap();
+            let presses = spec.solve_lights().unwrap().unwrap();
+            let moves = |presses: Vec<u64>| {
+                assert!(spec.verify(&presses).lights, "{machine}");
+                presses.iter().sum::<u64>()
+            };
+            let expected = moves(presses);
+            assert_eq!(MachinePart1::<u64>::new(&spec).search().map(moves), Some(expected), "{machine}");
+            assert_eq!(MachinePart1::<u128>::new(&spec).search().map(moves), Some(expected), "{machine}");
+            assert_eq!(MachinePart1::<Vec<u64>>::new(&spec).search().map(moves), Some(expected), "{machine}");
         }
 
-        let buttons = solution.get_solution().columns().iter().map(|&v| v.round() as u64).collect_vec();
-        let sum = buttons.iter().sum::<u64>();
-        part2_total += sum;
+        let unsolvable = MachineSpec::from_str("[#.] (1) {1,1}").unwrap();
+        let reason = Infeasible::Lights(gf2::Inconsistent { rows: vec![0] });
+        assert_eq!(unsolvable.search_lights(), Err(reason.clone()));
+        assert_eq!(unsolvable.solve_lights().unwrap(), Err(reason));
+    }
+
+    #[test]
+    fn verify_presses() {
+        let spec = MachineSpec::from_str("[.##.] (3) (1,3) (2) (2,3) (0,2) (0,1) {3,5,4,7}").unwrap();
+        assert_eq!(spec.verify(&[0, 0, 0, 0, 1, 1]), Verified { lights: true, voltages: false });
+        assert_eq!(spec.verify(&[1, 3, 0, 3, 1, 2]), Verified { lights: false, voltages: true });
+        assert_eq!(spec.simulate(&[0, 1, 0, 0, 0, 2]), (vec![false, true, false, true], vec![2, 3, 0, 1]));
+        assert_eq!(spec.describe_presses(&[1, 3, 0, 3, 1, 2]), "(3) x1, (1,3) x3, (2,3) x3, (0,2) x1, (0,1) x2");
+    }
+
+    #[test]
+    fn explain_infeasible() {
+        let explain = |machine: &str, lights: bool| {
+            let spec = MachineSpec::from_str(machine).unwrap();
+            let reason = if lights { spec.solve_lights().unwrap() } else { spec.solve_voltages() };
+            reason.unwrap_err().to_string()
+        };
+        assert_eq!(explain("[#..] (0,1) (1,2) (0,2) {1,1,1}", true), "every button toggles an even number of lights 0, 1, 2, but an odd number of them should be on");
+        assert_eq!(explain("[..] (0) {1,1}", false), "no button raises voltage 1");
+        assert_eq!(explain("[..] (0,1) {1,2}", false), "no button changes -v0 + v1, but the voltages need it to be nonzero");
+        assert_eq!(explain("[..] (0,1) (1) {2,1}", false), "no whole number of presses reaches the voltages");
+    }
+
+    #[test]
+    fn wide_machine() {
+        // too many lights for a dense bitmap or a single word, solved by pressing the first and third buttons
+        let lights: String = (0..130).map(|i| if (2..128).contains(&i) { '.' } else { '#' }).collect();
+        let spec = MachineSpec::from_str(&format!("[{lights}] (0,1) (2,3) (128,129) (0,129) {{{}}}", ["1"; 130].join(","))).unwrap();
+        assert_eq!(spec.search_lights(), Ok(vec![1, 0, 1, 0]));
+        assert_eq!(spec.solve_lights().unwrap(), Ok(vec![1, 0, 1, 0]));
+    }
+
+    #[test]
+    fn redundant_buttons() {
+        // far too many buttons toggling the same light to try every combination of them
+        let spec = MachineSpec::from_str(&format!("[#.] {} (1) {{1,0}}", ["(0)"; 70].join(" "))).unwrap();
+        let presses = spec.solve_lights().unwrap().unwrap();
+        assert!(spec.verify(&presses).lights);
+        assert_eq!(presses.iter().sum::<u64>(), 1);
+    }
+
+    #[test]
+    fn many_buttons_and_lights() {
+        // too many free and pivot buttons to enumerate either
+        let mut rng = Rng::new(0xbf58476d1ce4e5b9);
+        let mut machine = || {
+            let buttons: Vec<Vec<usize>> = (0..60).map(|_| (0..30).filter(|_| rng.below(2) == 0).collect()).collect();
+            let pressed: Vec<u64> = (0..60).map(|_| (rng.below(20) == 0).into()).collect();
+            let lights = column_sums(&buttons, &pressed, 30).iter().map(|sum| sum % 2 == 1).collect();
+            (MachineSpec { lights, buttons, voltages: vec![0; 30] }, pressed)
+        };
 
-        if verbosity > 0 {
-            println!("{spec} solution: {buttons:?} ({sum} moves)");
+        // lit by pressing a few buttons, so found by meeting in the middle
+        for _ in 0..2 {
+            let (spec, pressed) = machine();
+            let presses = spec.solve_lights().unwrap().unwrap();
+            assert!(spec.verify(&presses).lights, "{spec}");
+            assert!(presses.iter().sum::<u64>() <= pressed.iter().sum(), "{spec}");
         }
+        // two buttons for each light, all on, needing far too many presses to search for
+        let buttons = (0..30).map(|i| format!("({i}) ({i})")).join(" ");
+        let spec = MachineSpec::from_str(&format!("[{}] {buttons} {{{}}}", "#".repeat(30), ["0"; 30].join(","))).unwrap();
+        assert!(matches!(spec.solve_lights(), Err(Error::Solver(_))));
     }
-    println!("Part 2 total: {part2_total}");
-    Ok(())
-}
\ No newline at end of file
+}
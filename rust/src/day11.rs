@@ -1,11 +1,11 @@
-use std::{collections::HashMap, fs::File, io::{BufRead, BufReader}};
+use std::{collections::HashMap, io::BufRead};
 
 use petgraph::{Direction::Outgoing, algo::toposort, graph::{DiGraph, NodeIndex}};
 
-use super::input_dir;
+use super::{verbosity, Error, Result, Solution};
 
 
-pub fn parse_dag<R: BufRead>(file: R) -> Result<(DiGraph<(), ()>, HashMap<Box<str>, NodeIndex<u32>>), String> {
+pub fn parse_dag<R: BufRead>(file: R) -> Result<Devices> {
     let mut graph = DiGraph::new();
     let mut node_map: HashMap<Box<str>, NodeIndex<u32>> = HashMap::new();
 
@@ -19,11 +19,11 @@ pub fn parse_dag<R: BufRead>(file: R) -> Result<(DiGraph<(), ()>, HashMap<Box<st
 
     let mut edges: Vec<(NodeIndex<u32>, NodeIndex<u32>)> = Vec::new();
 
-    for line in file.lines() {
-        let line = line.map_err(|e| format!("Error reading file: {e}"))?;
-        if line.trim().len() == 0 { continue; }
+    for (i, line) in file.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() { continue; }
 
-        let (node, outgoing) = line.split_once(": ").ok_or_else(|| format!("Invalid line: {line}"))?;
+        let (node, outgoing) = line.split_once(": ").ok_or_else(|| Error::parse("Invalid line", line.as_str()).at_line(i + 1))?;
         let outgoing: Vec<_> = outgoing.split_whitespace().collect();
 
         let node = get_node(node);
@@ -33,58 +33,96 @@ pub fn parse_dag<R: BufRead>(file: R) -> Result<(DiGraph<(), ()>, HashMap<Box<st
     }
 
     edges.into_iter().for_each(|(i, o)| { graph.add_edge(i, o, ()); });
-    Ok((graph, node_map))
+    Ok(Devices { graph, node_map })
 }
 
 
-pub fn run(test: bool) -> Result<(), String> {
-    let mut input_path = input_dir().to_owned();
-    input_path.push(if test { "day11_test.txt" } else { "day11.txt" });
+pub struct Devices {
+    pub graph: DiGraph<(), ()>,
+    pub node_map: HashMap<Box<str>, NodeIndex<u32>>,
+}
 
-    let file = File::open(input_path).expect("Failed to open input file");
+impl Devices {
+    pub fn node(&self, name: &str) -> Result<NodeIndex<u32>> {
+        self.node_map.get(name).copied().ok_or_else(|| Error::Unsolvable(format!("Couldn't find '{name}' node")))
+    }
 
-    let (graph, node_map) = parse_dag(BufReader::new(file))?;
+    pub fn name(&self, node: NodeIndex<u32>) -> &str {
+        self.node_map.iter().find(|(_, &idx)| idx == node).map(|(name, _)| &**name).unwrap_or("?")
+    }
 
-    let you_node = *node_map.get("you").ok_or_else(|| format!("Couldn't find 'you' node"))?;
-    let out_node = *node_map.get("out").ok_or_else(|| format!("Couldn't find 'out' node"))?;
+    pub fn visit_order(&self) -> Result<Vec<NodeIndex<u32>>> {
+        toposort(&self.graph, None).map_err(|cycle| {
+            Error::Unsolvable(format!("Cyclic graph (through '{}')", self.name(cycle.node_id())))
+        })
+    }
+}
 
-    println!("nodes: {}\nedges: {}", graph.node_count(), graph.edge_count());
+#[derive(Debug, Clone, clap::Args)]
+pub struct Day11;
 
-    let mut weights = vec![0u64; graph.node_count()];
-    weights[you_node.index()] = 1;
+impl Solution for Day11 {
+    const DAY: u8 = 11;
 
-    let visit_order = toposort(&graph, None).map_err(|_| format!("Cyclic graph"))?;
+    type Input = Devices;
+    type Part1 = u64;
+    type Part2 = u64;
 
-    for node in visit_order.iter().copied() {
-        //println!("Visiting {}...", node.index());
-        for out in graph.neighbors_directed(node, Outgoing) {
-            weights[out.index()] += weights[node.index()];
+    fn parse<R: BufRead>(&self, input: R) -> Result<Self::Input> {
+        let devices = parse_dag(input)?;
+        if verbosity() > 0 {
+            println!("nodes: {}\nedges: {}", devices.graph.node_count(), devices.graph.edge_count());
         }
+        Ok(devices)
     }
-    println!("Part 1 # paths: {}", weights[out_node.index()]);
-
-    let dac_node = *node_map.get("dac").ok_or_else(|| format!("Couldn't find 'dac' node"))?;
-    let fft_node = *node_map.get("fft").ok_or_else(|| format!("Couldn't find 'fft' node"))?;
-    let svr_node = *node_map.get("svr").ok_or_else(|| format!("Couldn't find 'svr' node"))?;
-
-    // none, one, both
-    let mut weights = vec![[0u64; 3]; graph.node_count()];
-    weights[svr_node.index()][0] += 1;
-
-    for node in visit_order.into_iter() {
-        let mut in_weight = weights[node.index()];
-        if node == fft_node || node == dac_node {
-            //println!("weight at fft/dac node: {:?}", in_weight);
-            // none => one, one => both
-            in_weight = [0, in_weight[0], in_weight[1] + in_weight[2]];
-        }
 
-        for out in graph.neighbors_directed(node, Outgoing) {
-            weights[out.index()].iter_mut().zip(in_weight).for_each(|(l, r)| *l += r);
+    fn part1(&self, devices: &Self::Input) -> Result<u64> {
+        let graph = &devices.graph;
+        let you_node = devices.node("you")?;
+        let out_node = devices.node("out")?;
+
+        let mut weights = vec![0u64; graph.node_count()];
+        weights[you_node.index()] = 1;
+
+        let visit_order = devices.visit_order()?;
+
+        for node in visit_order.iter().copied() {
+            //println!("Visiting {}...", node.index());
+            for out in graph.neighbors_directed(node, Outgoing) {
+                weights[out.index()] += weights[node.index()];
+            }
         }
+        Ok(weights[out_node.index()])
     }
-    println!("paths: {:?}", weights[out_node.index()]);
-    println!("Part 2 # paths: {}", weights[out_node.index()][2]);
 
-    Ok(())
-}
\ No newline at end of file
+    fn part2(&self, devices: &Self::Input) -> Result<u64> {
+        let graph = &devices.graph;
+        let out_node = devices.node("out")?;
+        let dac_node = devices.node("dac")?;
+        let fft_node = devices.node("fft")?;
+        let svr_node = devices.node("svr")?;
+
+        let visit_order = devices.visit_order()?;
+
+        // none, one, both
+        let mut weights = vec![[0u64; 3]; graph.node_count()];
+        weights[svr_node.index()][0] += 1;
+
+        for node in visit_order.into_iter() {
+            let mut in_weight = weights[node.index()];
+            if node == fft_node || node == dac_node {
+                //println!("weight at fft/dac node: {:?}", in_weight);
+                // none => one, one => both
+                in_weight = [0, in_weight[0], in_weight[1] + in_weight[2]];
+            }
+
+            for out in graph.neighbors_directed(node, Outgoing) {
+                weights[out.index()].iter_mut().zip(in_weight).for_each(|(l, r)| *l += r);
+            }
+        }
+        if verbosity() > 0 {
+            println!("paths: {:?}", weights[out_node.index()]);
+        }
+        Ok(weights[out_node.index()][2])
+    }
+}
@@ -1,8 +1,8 @@
-use std::{fs::File, io::{BufRead, BufReader}, iter::Peekable};
+use std::{io::BufRead, iter::Peekable};
 
 use ndarray::Array2;
 
-use super::input_dir;
+use super::{verbosity, Error, Result, Solution};
 
 
 #[derive(Debug)]
@@ -13,16 +13,17 @@ pub struct Tree {
 }
 
 
-pub fn parse_presents<'a, I: Iterator<Item=&'a String>>(
+/// Parse present shapes from numbered lines, stopping at the first tree
+pub fn parse_presents<'a, I: Iterator<Item=(usize, &'a String)>>(
     lines: &mut Peekable<I>
-) -> Result<Vec<Array2<bool>>, String> {
+) -> Result<Vec<Array2<bool>>> {
     let mut presents = Vec::new();
 
-    while let Some(line) = lines.peek() {
+    while let Some(&(i, line)) = lines.peek() {
         let line = line.trim();
-        if line.is_empty() { continue; }
+        if line.is_empty() { lines.next(); continue; }
 
-        let (before, after) = line.split_once(':').ok_or_else(|| format!("Invalid line: {line}"))?;
+        let (before, after) = line.split_once(':').ok_or_else(|| Error::parse("Invalid line", line).at_line(i + 1))?;
 
         if !after.is_empty() || before.contains('x') {
             // start of trees
@@ -34,12 +35,16 @@ pub fn parse_presents<'a, I: Iterator<Item=&'a String>>(
         let mut width: Option<usize> = None;
         let mut height: usize = 0;
 
-        while let Some(line) = lines.next() {
+        for (j, line) in lines.by_ref() {
             if line.trim().is_empty() { break; }
             let row: Vec<_> = line.trim().chars().map(|c| c == '#').collect();
 
             match width {
-                Some(w) => { if row.len() != w { return Err(format!("Invalid present, uneven widths")); } },
+                Some(w) => {
+                    if row.len() != w {
+                        return Err(Error::parse("Invalid present, uneven widths", line.as_str()).at_line(j + 1));
+                    }
+                },
                 None => { width = Some(row.len()) },
             }
 
@@ -47,7 +52,7 @@ pub fn parse_presents<'a, I: Iterator<Item=&'a String>>(
             height += 1;
         }
 
-        let width = width.ok_or_else(|| format!("Invalid present, missing any rows"))?;
+        let width = width.ok_or_else(|| Error::parse("Invalid present, missing any rows", line).at_line(i + 1))?;
         presents.push(Array2::from_shape_vec([height, width], present).unwrap());
     }
 
@@ -55,12 +60,12 @@ pub fn parse_presents<'a, I: Iterator<Item=&'a String>>(
 }
 
 
-pub fn parse_trees<'a, I: Iterator<Item=&'a String>>(
+pub fn parse_trees<'a, I: Iterator<Item=(usize, &'a String)>>(
     lines: &mut Peekable<I>
-) -> Result<Vec<Tree>, String> {
+) -> Result<Vec<Tree>> {
     let mut trees = Vec::new();
 
-    for line in lines {
+    for (i, line) in lines {
         if line.trim().is_empty() { continue; }
 
         trees.push(line.split_once(": ").and_then(|(before, after)| {
@@ -69,51 +74,158 @@ pub fn parse_trees<'a, I: Iterator<Item=&'a String>>(
             let size = size.try_into().ok()?;
 
             Some(Tree { size, presents })
-        }).ok_or_else(|| format!("Invalid tree: {line}"))?);
+        }).ok_or_else(|| Error::parse("Invalid tree", line.as_str()).at_line(i + 1))?);
     }
 
     Ok(trees)
 }
 
 
-pub fn run(test: bool) -> Result<(), String> {
-    let mut input_path = input_dir().to_owned();
-    input_path.push(if test { "day12_test.txt" } else { "day12.txt" });
+/// Distinct rotations and reflections of a present, each as `[y, x]` offsets of its cells from
+/// the first cell in reading order
+fn orientations(present: &Array2<bool>) -> Vec<Vec<[i64; 2]>> {
+    let cells: Vec<[i64; 2]> = present.indexed_iter()
+        .filter(|&(_, &filled)| filled).map(|((y, x), _)| [y as i64, x as i64]).collect();
+    let mut shapes: Vec<Vec<[i64; 2]>> = Vec::new();
+    for transform in 0..8 {
+        let mut shape: Vec<[i64; 2]> = cells.iter().map(|&[y, x]| {
+            let [y, x] = if transform & 1 == 1 { [x, y] } else { [y, x] };
+            [if transform & 2 == 2 { -y } else { y }, if transform & 4 == 4 { -x } else { x }]
+        }).collect();
+        shape.sort();
+        let Some(&[y0, x0]) = shape.first() else { continue };
+        for cell in shape.iter_mut() { *cell = [cell[0] - y0, cell[1] - x0]; }
+        if !shapes.contains(&shape) { shapes.push(shape); }
+    }
+    shapes
+}
+
+/// Backtracking search for an arrangement of presents under a tree
+struct Packing<'a> {
+    size: [usize; 2],
+    /// Cells covered by a present or left empty, in reading order
+    filled: Vec<bool>,
+    /// Orientations of each kind of present
+    shapes: &'a [Vec<Vec<[i64; 2]>>],
+    /// Presents of each kind still to place
+    counts: Vec<u64>,
+}
 
-    let file = File::open(input_path).expect("Failed to open input file");
+impl<'a> Packing<'a> {
+    fn new(size: [usize; 2], shapes: &'a [Vec<Vec<[i64; 2]>>], counts: Vec<u64>) -> Self {
+        Self { size, filled: vec![false; size[0] * size[1]], shapes, counts }
+    }
 
-    let (presents, trees) = {
-        let lines: Vec<String> = BufReader::new(file).lines()
-            .map(|r| r.map_err(|e| format!("Error reading file: {e}"))).collect::<Result<_, _>>()?;
+    /// Whether the remaining presents fit, covering or leaving empty each cell from `cell` in
+    /// reading order. At most `slack` cells can be left empty.
+    fn fill(&mut self, cell: usize, slack: u64) -> bool {
+        if self.counts.iter().all(|&n| n == 0) { return true; }
+        let Some(cell) = (cell..self.filled.len()).find(|&i| !self.filled[i]) else { return false };
+        let [height, width] = self.size;
+        let (y, x) = ((cell / width) as i64, (cell % width) as i64);
+
+        for kind in 0..self.shapes.len() {
+            if self.counts[kind] == 0 { continue; }
+            for shape in &self.shapes[kind] {
+                // the first cell of the shape goes in the first free cell, as all before it are filled
+                let index = |&[dy, dx]: &[i64; 2]| {
+                    let (y, x) = (y + dy, x + dx);
+                    let inside = (0..height as i64).contains(&y) && (0..width as i64).contains(&x);
+                    inside.then(|| y as usize * width + x as usize)
+                };
+                if !shape.iter().all(|offset| index(offset).is_some_and(|i| !self.filled[i])) { continue; }
+
+                for i in shape.iter().filter_map(index) { self.filled[i] = true; }
+                self.counts[kind] -= 1;
+                let found = self.fill(cell + 1, slack);
+                self.counts[kind] += 1;
+                for i in shape.iter().filter_map(index) { self.filled[i] = false; }
+                if found { return true; }
+            }
+        }
+
+        // or leave the cell empty
+        slack > 0 && {
+            self.filled[cell] = true;
+            let found = self.fill(cell + 1, slack - 1);
+            self.filled[cell] = false;
+            found
+        }
+    }
+}
 
-        let mut iter = lines.iter().peekable();
-        (parse_presents(&mut iter)?, parse_trees(&mut iter)?)
-    };
 
-    let areas: Vec<_> = presents.iter()
-        .map(|present| present.iter().map(|&v| v as u64).sum::<u64>()).collect();
+#[derive(Debug, Clone, clap::Args)]
+pub struct Day12;
 
-    let mut n_impossible = 0u64;
-    let mut n_possible = 0u64;
-    let mut n_unknown = 0u64;
+impl Solution for Day12 {
+    const DAY: u8 = 12;
 
-    for tree in trees {
-        let n_presents: u64 = tree.presents.iter().sum();
-        let required_area: u64 = tree.presents.iter().enumerate().map(|(i, n)| n * areas[i]).sum();
-        let n_tiles: u64 = tree.size.iter().map(|v| v / 3).product();
+    type Input = (Vec<Array2<bool>>, Vec<Tree>);
+    type Part1 = u64;
+    type Part2 = ();
 
-        if required_area > tree.size.iter().product() {
-            n_impossible += 1;
-            println!("Impossible: Area doesn't fit");
-        } else if n_presents <= n_tiles {
-            n_possible += 1;
-            println!("Trivial: Bounding boxes fit");
-        } else {
-            n_unknown += 1;
-            println!("Unknown");
+    fn parse<R: BufRead>(&self, input: R) -> Result<Self::Input> {
+        let lines: Vec<String> = input.lines().collect::<std::io::Result<_>>()?;
+
+        let mut iter = lines.iter().enumerate().peekable();
+        let presents = parse_presents(&mut iter)?;
+        let trees = parse_trees(&mut iter)?;
+
+        if let Some(tree) = trees.iter().find(|tree| tree.presents.len() > presents.len()) {
+            return Err(Error::parse(
+                format!("Tree lists {} kinds of presents, but only {} are defined", tree.presents.len(), presents.len()),
+                format!("{}x{}", tree.size[1], tree.size[0]),
+            ));
+        }
+        Ok((presents, trees))
+    }
+
+    fn part1(&self, (presents, trees): &Self::Input) -> Result<u64> {
+        let verbosity = verbosity();
+
+        let areas: Vec<_> = presents.iter()
+            .map(|present| present.iter().map(|&v| v as u64).sum::<u64>()).collect();
+
+        let shapes: Vec<_> = presents.iter().map(orientations).collect();
+
+        let mut n_impossible = 0u64;
+        let mut n_possible = 0u64;
+
+        for tree in trees {
+            let n_presents: u64 = tree.presents.iter().sum();
+            let required_area: u64 = tree.presents.iter().enumerate().map(|(i, n)| n * areas[i]).sum();
+            let tree_area: u64 = tree.size.iter().product();
+            let n_tiles: u64 = tree.size.iter().map(|v| v / 3).product();
+
+            if required_area > tree_area {
+                n_impossible += 1;
+                if verbosity > 0 { println!("Impossible: Area doesn't fit"); }
+            } else if n_presents <= n_tiles {
+                n_possible += 1;
+                if verbosity > 0 { println!("Trivial: Bounding boxes fit"); }
+            } else {
+                let mut counts = tree.presents.clone();
+                counts.resize(presents.len(), 0);
+                let size = tree.size.map(|v| v as usize);
+                if Packing::new(size, &shapes, counts).fill(0, tree_area - required_area) {
+                    n_possible += 1;
+                    if verbosity > 0 { println!("Possible: Found an arrangement"); }
+                } else {
+                    n_impossible += 1;
+                    if verbosity > 0 { println!("Impossible: No arrangement fits"); }
+                }
+            }
+        }
+        if verbosity > 0 {
+            println!("Possible: {n_possible}, Impossible: {n_impossible}");
         }
+
+        Ok(n_possible)
     }
-    println!("Possible: {n_possible}, Impossible: {n_impossible}, unknown: {n_unknown}");
 
-    Ok(())
-}
\ No newline at end of file
+    fn part2(&self, _input: &Self::Input) -> Result<()> {
+        // no part 2 on the last day
+        Ok(())
+    }
+}
@@ -1,16 +1,16 @@
-use std::{fs::File, io::{BufReader, BufRead}};
+use std::io::BufRead;
 
-use super::{input_dir, verbosity};
+use super::{verbosity, Error, Result, Solution};
 
-fn parse_range<'a>(range: &'a str) -> Result<(u64, u64), String> {
+fn parse_range(range: &str) -> Option<(u64, u64)> {
     range.trim().split_once('-').and_then(|(l, r)| match (l.parse(), r.parse()) {
         (Ok(l), Ok(r)) => Some((l, r)),
         _ => None,
-    }).ok_or_else(|| format!("Invalid range '{}'", range))
+    })
 }
 
 fn is_repeated(s: &str, n: usize) -> bool {
-    if s.len() % n != 0 { return false; }
+    if !s.len().is_multiple_of(n) { return false; }
     s[n..].bytes().zip(
         std::iter::repeat(&s[..n]).flat_map(|s| s.bytes())
     ).all(|(l, r)| l == r)
@@ -24,41 +24,73 @@ fn check_range(range: (u64, u64), verbosity: u8) -> (u64, u64) {
     }
     for id in range.0..=range.1 {
         let s = id.to_string();
-        for repeat_len in 1..=s.len()/2 {
-            if is_repeated(&s, repeat_len) {
-                if verbosity > 0 {
-                    println!("  invalid id {}", id);
-                }
-                part2_sum = part2_sum.checked_add(id).expect("Overflow");
-                if repeat_len == s.len()/2  { part1_sum += id; }
-                break;
+        // part 1 only counts ids repeated exactly twice, which may also have a shorter repeat (222222)
+        if s.len().is_multiple_of(2) && is_repeated(&s, s.len() / 2) { part1_sum += id; }
+        if (1..=s.len()/2).any(|repeat_len| is_repeated(&s, repeat_len)) {
+            if verbosity > 0 {
+                println!("  invalid id {}", id);
             }
+            part2_sum = part2_sum.checked_add(id).expect("Overflow");
         }
     }
     (part1_sum, part2_sum)
 }
 
-pub fn run() -> Result<(), String> {
-    let verbosity = verbosity();
+#[derive(Debug, Clone, clap::Args)]
+pub struct Day2;
 
-    let mut input_path = input_dir().to_owned();
-    input_path.push("day2.txt");
+impl Solution for Day2 {
+    const DAY: u8 = 2;
 
-    let file = File::open(input_path).expect("Failed to open input file");
+    type Input = Vec<(u64, u64)>;
+    type Part1 = u64;
+    type Part2 = u64;
 
-    let mut part1_sum: u64 = 0;
-    let mut part2_sum: u64 = 0;
+    fn parse<R: BufRead>(&self, input: R) -> Result<Self::Input> {
+        // single line of comma-separated ranges
+        let mut column = 1;
+        input.split(b',').map(|range| {
+            let range = range?;
+            let range_column = column;
+            column += range.len() + 1;
+
+            let s = str::from_utf8(&range).map_err(|_| Error::parse("Invalid utf-8 in file", "").at_line(1).at_column(range_column))?;
+            parse_range(s).ok_or_else(|| Error::parse("Invalid range", s.trim()).at_line(1).at_column(range_column))
+        }).collect()
+    }
+
+    fn part1(&self, input: &Self::Input) -> Result<u64> {
+        let verbosity = verbosity();
+        Ok(input.iter().map(|&range| check_range(range, verbosity).0).sum())
+    }
+
+    fn part2(&self, input: &Self::Input) -> Result<u64> {
+        let mut part2_sum: u64 = 0;
+        for &range in input {
+            let (_, part2) = check_range(range, 0);
+            part2_sum = part2_sum.checked_add(part2).ok_or_else(|| Error::Unsolvable("Sum overflows u64".to_owned()))?;
+        }
+        Ok(part2_sum)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for range in BufReader::new(file).split(b',') {
-        let range = parse_range(
-            range.as_ref().map_err(|e| format!("Error reading file: {}", e))
-                .and_then(|b| str::from_utf8(b).map_err(|_| format!("Invalid utf-8 in file")))?
-        )?;
-        let (part1, part2) = check_range(range, verbosity);
-        part2_sum = part2_sum.checked_add(part2).ok_or_else(|| format!("Overflow"))?;
-        part1_sum += part1;
+    #[test]
+    fn repeated() {
+        assert!(is_repeated("1212", 2));
+        assert!(is_repeated("123123123", 3));
+        assert!(is_repeated("777", 1));
+        assert!(!is_repeated("1213", 2));
+        assert!(!is_repeated("12121", 2));
     }
-    println!("Part 1 sum: {}", part1_sum);
-    println!("Part 2 sum: {}", part2_sum);
-    Ok(())
-}
\ No newline at end of file
+
+    #[test]
+    fn repeated_twice_with_shorter_repeat() {
+        // 222222 is both "222" twice and "2" six times
+        assert_eq!(check_range((222220, 222224), 0), (222222, 222222));
+        assert_eq!(check_range((111, 111), 0), (0, 111));
+    }
+}
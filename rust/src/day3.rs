@@ -1,6 +1,6 @@
-use std::{fs::File, io::{BufRead, BufReader}};
+use std::io::BufRead;
 
-use super::{input_dir, verbosity};
+use super::{verbosity, Error, Result, Solution};
 
 #[allow(unused)]
 fn bank_voltage_part1(bank: &[u8]) -> u64 {
@@ -42,37 +42,45 @@ fn bank_voltage_part2(bank: &[u8], n: u8) -> u64 {
 }
 
 
-pub fn run(test: bool) -> Result<(), String> {
-    let verbosity = verbosity();
-
-    let mut input_path = input_dir().to_owned();
-    input_path.push(if test { "day3_test.txt" } else { "day3.txt" });
-
-    let file = File::open(input_path).map_err(|e| format!("Failed to open input file: {e}"))?;
-
-    let mut part1_sum = 0u64;
-    let mut part2_sum = 0u64;
-
-    for line in BufReader::new(file).lines() {
-        let line = line.map_err(|e| format!("Error reading file: {e}"))?;
-        let bank: Vec<u8> = line.bytes()
-            // ascii digit to u8
-            .map(|c| if c >= 48 && c < 58 { Some(c - 48) } else { None } )
-            .collect::<Option<Vec<u8>>>().ok_or_else(|| format!("Invalid bank '{line}'"))?;
+#[derive(Debug, Clone, clap::Args)]
+pub struct Day3;
+
+impl Solution for Day3 {
+    const DAY: u8 = 3;
+
+    type Input = Vec<Vec<u8>>;
+    type Part1 = u64;
+    type Part2 = u64;
+
+    fn parse<R: BufRead>(&self, input: R) -> Result<Self::Input> {
+        input.lines().enumerate().map(|(i, line)| {
+            let line = line?;
+            line.bytes().enumerate()
+                // ascii digit to u8
+                .map(|(j, c)| if c.is_ascii_digit() {
+                    Ok(c - 48)
+                } else {
+                    Err(Error::parse("Invalid battery in bank", line.as_str()).at_line(i + 1).at_column(j + 1))
+                })
+                .collect()
+        }).collect()
+    }
 
-        let part1_max = bank_voltage_part2(&bank, 2);
-        part1_sum += part1_max;
+    fn part1(&self, input: &Self::Input) -> Result<u64> {
+        Ok(total_voltage(input, 2, verbosity()))
+    }
 
-        let part2_max = bank_voltage_part2(&bank, 12);
-        part2_sum += part2_max;
+    fn part2(&self, input: &Self::Input) -> Result<u64> {
+        Ok(total_voltage(input, 12, verbosity()))
+    }
+}
 
+fn total_voltage(banks: &[Vec<u8>], n: u8, verbosity: u8) -> u64 {
+    banks.iter().map(|bank| {
+        let max = bank_voltage_part2(bank, n);
         if verbosity > 0 {
-            println!("{line}: part 1: {part1_max} part 2: {part2_max}")
+            println!("{}: {n} batteries: {max}", bank.iter().map(|d| (d + 48) as char).collect::<String>());
         }
-    }
-
-    println!("Part 1 sum: {part1_sum}");
-    println!("Part 2 sum: {part2_sum}");
-
-    Ok(())
-}
\ No newline at end of file
+        max
+    }).sum()
+}
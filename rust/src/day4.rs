@@ -1,22 +1,27 @@
-use std::{fs::File, io::{BufRead, BufReader}, path::Path};
+use std::io::BufRead;
 
 use ndarray::{Array1, Array2, ArrayBase, Axis, Data, Dim, stack};
 use ndarray_conv::{ConvExt, ConvMode, PaddingMode};
 
-use super::{input_dir, verbosity};
+use super::{verbosity, Error, Result, Solution};
 
 
-fn load_array<P: AsRef<Path>>(path: P) -> Result<Array2<u8>, String> {
-    let file = File::open(path).map_err(|e| format!("Failed to open input file: {e}"))?;
-    let mut rows = Vec::new();
+fn load_array<R: BufRead>(input: R) -> Result<Array2<u8>> {
+    let mut rows: Vec<Array1<u8>> = Vec::new();
 
-    for line in BufReader::new(file).lines() {
-        let line = line.map_err(|e| format!("Error reading file: {e}"))?;
+    for (i, line) in input.lines().enumerate() {
+        let line = line?;
+        match rows.first() {
+            Some(first) if first.len() != line.len() => return Err(Error::parse(
+                format!("Row length {} doesn't match first row ({})", line.len(), first.len()), line
+            ).at_line(i + 1)),
+            _ => (),
+        }
         rows.push(Array1::from_iter(line.bytes().map(|c| (c == b'@') as u8)))
     }
 
     let views: Vec<_> = rows.iter().map(|arr| arr.view()).collect();
-    stack(Axis(0), &views).map_err(|_| format!("Mismatched shapes in rows"))
+    stack(Axis(0), &views).map_err(|_| Error::parse("Empty grid", ""))
 }
 
 fn print_array<'a, S>(arr: ArrayBase<S, Dim<[usize; 2]>>)
@@ -26,7 +31,7 @@ where S: Data<Elem = u8> + 'a
         let line: String = line.iter().map(|c| if *c > 0 { '@' } else { '.' }).collect();
         println!("{}", line)
     }
-    println!("")
+    println!()
 }
 
 #[allow(unused)]
@@ -40,7 +45,27 @@ where S: Data<Elem = u8> + 'a
         }).collect();
         println!("{}", line)
     }
-    println!("")
+    println!()
+}
+
+/// Boxes with fewer than 4 neighbors
+pub fn removable(arr: &Array2<u8>, verbosity: u8) -> Array2<u8> {
+    // kernel is separable, use 2 1D convolutions
+    let kernel1: Array2<u8> = Array2::ones((3, 1));
+    let kernel2: Array2<u8> = Array2::ones((1, 3));
+
+    let convolved = ConvExt::conv(
+        &ConvExt::conv(arr, &kernel1, ConvMode::Same, PaddingMode::Zeros).unwrap(),
+        &kernel2, ConvMode::Same, PaddingMode::Zeros
+    ).unwrap();
+    if verbosity > 1 {
+        print_counts(convolved.view());
+    }
+
+    // 4 neighbors + self
+    let mut available = convolved.mapv(|c| if c < 5 { 1u8 } else { 0u8 });
+    available &= arr;
+    available
 }
 
 pub fn process(mut arr: Array2<u8>, verbosity: u8) -> u64 {
@@ -52,49 +77,48 @@ pub fn process(mut arr: Array2<u8>, verbosity: u8) -> u64 {
         print_array(arr.view());
     }
 
-    // kernel is separable, use 2 1D convolutions
-    let kernel1: Array2<u8> = Array2::ones((3, 1));
-    let kernel2: Array2<u8> = Array2::ones((1, 3));
-
     let mut i = 0;
 
     loop {
-        let convolved = ConvExt::conv(
-            &ConvExt::conv(&arr, &kernel1, ConvMode::Same, PaddingMode::Zeros).unwrap(),
-            &kernel2, ConvMode::Same, PaddingMode::Zeros
-        ).unwrap();
-        if verbosity > 1 {
-            print_counts(convolved.view());
-        }
-
-        // 4 neighbors + self
-        let mut available = convolved.mapv(|c| if c < 5 { 1u8 } else { 0u8 });
-        available &= &arr;
+        let available = removable(&arr, verbosity);
         let n_available: u64 = available.iter().map(|c| *c as u64).sum();
         if n_available == 0 { break; }
 
         i += 1;
-        println!("Step {i}, removed {n_available:3} box(es)");
+        if verbosity > 0 { println!("Step {i}, removed {n_available:3} box(es)"); }
         arr ^= &available;
         n -= n_available;
         if verbosity > 0 { print_array(arr.view()); }
     }
 
-    println!("Finished in {i} step(s), final state:");
-    print_array(arr);
-    println!("{n_start} -> {n} boxes (removed {})", n_start - n);
+    if verbosity > 0 {
+        println!("Finished in {i} step(s), final state:");
+        print_array(arr);
+        println!("{n_start} -> {n} boxes (removed {})", n_start - n);
+    }
     n_start - n
 }
 
 
-pub fn run(test: bool) -> Result<(), String> {
-    let verbosity = verbosity();
+#[derive(Debug, Clone, clap::Args)]
+pub struct Day4;
 
-    let mut input_path = input_dir().to_owned();
-    input_path.push(if test { "day4_test.txt" } else { "day4.txt" });
+impl Solution for Day4 {
+    const DAY: u8 = 4;
 
-    let arr = load_array(&input_path)?;
-    process(arr, verbosity);
+    type Input = Array2<u8>;
+    type Part1 = u64;
+    type Part2 = u64;
 
-    Ok(())
-}
\ No newline at end of file
+    fn parse<R: BufRead>(&self, input: R) -> Result<Self::Input> {
+        load_array(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Result<u64> {
+        Ok(removable(input, verbosity()).iter().map(|c| *c as u64).sum())
+    }
+
+    fn part2(&self, input: &Self::Input) -> Result<u64> {
+        Ok(process(input.clone(), verbosity()))
+    }
+}
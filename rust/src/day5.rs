@@ -1,22 +1,25 @@
-use std::{fs::File, io::{BufRead, BufReader}};
+use std::io::{self, BufRead};
 
-use super::{input_dir, verbosity};
+use super::{verbosity, Error, Result, Solution};
 
 
-pub fn read_intervals<R: BufRead>(file: &mut R, verbosity: u8) -> Result<Vec<(u64, u64)>, String> {
+/// Read intervals from numbered lines, up to the first blank line
+pub fn read_intervals<I>(lines: &mut I, verbosity: u8) -> Result<Vec<(u64, u64)>>
+where I: Iterator<Item = (usize, io::Result<String>)>
+{
     let mut intervals = Vec::new();
 
-    for line in file.lines() {
-        let line = line.map_err(|e| format!("Error reading file: {e}"))?;
+    for (i, line) in lines {
+        let line = line?;
         let trim = line.trim();
-        if trim.len() == 0 {
+        if trim.is_empty() {
             break
         }
 
         let interval: (u64, u64) = trim.split_once('-').and_then(|(l, r)| match (l.parse(), r.parse()) {
             (Ok(l), Ok(r)) => Some((l, r)),
             _ => None,
-        }).ok_or_else(|| format!("Invalid range '{}'", trim))?;
+        }).ok_or_else(|| Error::parse("Invalid range", trim).at_line(i + 1))?;
 
         // maintain sort order
         intervals.insert(
@@ -87,37 +90,70 @@ pub fn check_id(intervals: &[(u64, u64)], id: u64, verbosity: u8) -> bool {
     }
 }
 
-pub fn run(test: bool) -> Result<(), String> {
-    let verbosity = verbosity();
+pub struct Inventory {
+    /// Sorted, disjoint fresh ID ranges
+    pub intervals: Vec<(u64, u64)>,
+    pub ids: Vec<u64>,
+}
 
-    let mut input_path = input_dir().to_owned();
-    input_path.push(if test { "day5_test.txt" } else { "day5.txt" });
+#[derive(Debug, Clone, clap::Args)]
+pub struct Day5;
 
-    let file = File::open(input_path).expect("Failed to open input file");
-    let mut buf = BufReader::new(file);
+impl Solution for Day5 {
+    const DAY: u8 = 5;
 
-    let intervals = read_intervals(&mut buf, verbosity)?;
+    type Input = Inventory;
+    type Part1 = u64;
+    type Part2 = u64;
 
-    if verbosity > 1 {
-        println!("Final intervals {intervals:?}");
-    }
+    fn parse<R: BufRead>(&self, input: R) -> Result<Self::Input> {
+        let verbosity = verbosity();
+        let mut lines = input.lines().enumerate();
+        let intervals = read_intervals(&mut lines, verbosity)?;
 
-    let mut sum = 0u64;
+        if verbosity > 1 {
+            println!("Final intervals {intervals:?}");
+        }
 
-    for line in buf.lines() {
-        let line = line.map_err(|e| format!("Error reading file: {e}"))?; 
-        let id = line.trim().parse().map_err(|_| format!("Invalid id: {line}"))?;
+        let ids = lines.map(|(i, line)| {
+            let line = line?;
+            line.trim().parse().map_err(|_| Error::parse("Invalid id", line.trim()).at_line(i + 1))
+        }).collect::<Result<_>>()?;
 
-        if check_id(&intervals, id, verbosity) {
-            sum += 1;
-        }
+        Ok(Inventory { intervals, ids })
+    }
+
+    fn part1(&self, input: &Self::Input) -> Result<u64> {
+        let verbosity = verbosity();
+        Ok(input.ids.iter().filter(|&&id| check_id(&input.intervals, id, verbosity)).count() as u64)
     }
 
-    println!("# fresh IDs: {sum}");
+    fn part2(&self, input: &Self::Input) -> Result<u64> {
+        // since intervals are disjoint, we're good
+        Ok(input.intervals.iter().map(|(start, end)| end - start + 1).sum())
+    }
+}
 
-    // since intervals are disjoint, we're good
-    let total_fresh: u64 = intervals.iter().map(|(start, end)| end - start + 1).sum();
-    println!("total # of fresh: {total_fresh}");
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(())
-}
\ No newline at end of file
+    #[test]
+    fn deduplicate_merges_overlaps() {
+        assert_eq!(deduplicate(vec![], 0), vec![]);
+        assert_eq!(deduplicate(vec![(3, 5), (10, 14), (12, 18), (16, 20)], 0), vec![(3, 5), (10, 20)]);
+        // contained and touching intervals
+        assert_eq!(deduplicate(vec![(1, 10), (2, 3), (10, 12), (13, 13)], 0), vec![(1, 12), (13, 13)]);
+    }
+
+    #[test]
+    fn check_id_bounds() {
+        let intervals = [(3, 5), (10, 20)];
+        assert!(!check_id(&intervals, 1, 0));
+        assert!(check_id(&intervals, 3, 0));
+        assert!(check_id(&intervals, 5, 0));
+        assert!(!check_id(&intervals, 8, 0));
+        assert!(check_id(&intervals, 20, 0));
+        assert!(!check_id(&intervals, 21, 0));
+    }
+}
@@ -1,8 +1,8 @@
-use std::{ascii, fs::File, io::{BufRead, BufReader, Seek, SeekFrom}, mem};
+use std::{ascii, io::BufRead, mem};
 
 use ndarray::{Array2, Axis};
 
-use super::{input_dir, verbosity};
+use super::{verbosity, Error, Result, Solution};
 
 #[derive(Debug, Clone, Copy)]
 enum Operator {
@@ -10,13 +10,23 @@ enum Operator {
     Plus,
 }
 
-struct Problem {
+pub struct Problem {
     arguments: Vec<Vec<u64>>,
     operators: Vec<Operator>,
 }
 
+fn parse_operators(line: &str, line_no: usize) -> Result<Vec<Operator>> {
+    line.split_whitespace().map(|word| {
+        match word {
+            "*" => Ok(Operator::Times),
+            "+" => Ok(Operator::Plus),
+            _ => Err(Error::parse("Invalid operator", word).at_line(line_no).at_word(line, word)),
+        }
+    }).collect()
+}
+
 impl Problem {
-    pub fn parse_part1<R: BufRead>(mut file: R) -> Result<Problem, String> {
+    pub fn parse_part1<R: BufRead>(mut file: R) -> Result<Problem> {
         let mut n_rows: usize = 0;
 
         let mut arguments: Vec<u64> = Vec::new();
@@ -24,32 +34,30 @@ impl Problem {
 
         loop {
             line.clear();
-            file.read_line(&mut line).map_err(|e| format!("Error reading file: {e}"))?;
+            file.read_line(&mut line)?;
 
             if let Some('*' | '+') | None = line.trim_start().chars().next() {
                 break;
             }
 
             for word in line.split_whitespace() {
-                arguments.push(word.parse().map_err(|_| format!("Invalid argument: '{word}'"))?);
+                arguments.push(word.parse().map_err(|_| {
+                    Error::parse("Invalid argument", word).at_line(n_rows + 1).at_word(&line, word)
+                })?);
             }
 
             n_rows += 1;
         }
 
-        if line.len() == 0 {
-            return Err("File missing operator row".to_owned());
+        if line.is_empty() {
+            return Err(Error::parse("File missing operator row", "").at_line(n_rows + 1));
         }
 
-        let operators: Vec<Operator> = line.split_whitespace().map(|word| {
-            match word {
-                "*" => Ok(Operator::Times),
-                "+" => Ok(Operator::Plus),
-                _ => Err(format!("Invalid operator '{word}'")),
-            }
-        }).collect::<Result<Vec<Operator>, String>>()?;
+        let operators = parse_operators(&line, n_rows + 1)?;
 
-        let arguments = Array2::from_shape_vec((n_rows, operators.len()), arguments).map_err(|_| format!("Invalid shape"))?;
+        let arguments = Array2::from_shape_vec((n_rows, operators.len()), arguments).map_err(|_| {
+            Error::parse("Number of arguments doesn't match number of operators", "")
+        })?;
 
         Ok(Self {
             arguments: arguments.axis_iter(Axis(1)).map(|v| v.to_vec()).collect(), // transpose, keep row major
@@ -57,43 +65,39 @@ impl Problem {
         })
     }
 
-    pub fn parse_part2<R: BufRead>(mut file: R) -> Result<Problem, String> {
+    pub fn parse_part2<R: BufRead>(mut file: R) -> Result<Problem> {
         let mut n_rows: usize = 0;
         let mut lines: Vec<ascii::Char> = Vec::new();
 
         let mut line = String::new();
         loop {
             line.clear();
-            file.read_line(&mut line).map_err(|e| format!("Error reading file: {e}"))?;
+            file.read_line(&mut line)?;
             if line.ends_with('\n') { line.pop(); }
 
             if let Some('*' | '+') | None = line.trim_start().chars().next() {
                 break;
             }
-            lines.extend(line.as_ascii().ok_or_else(|| format!("Non-ascii chars in file"))?);
+            lines.extend(line.as_ascii().ok_or_else(|| Error::parse("Non-ascii chars in line", line.as_str()).at_line(n_rows + 1))?);
             n_rows += 1;
         }
 
-        let operators: Vec<Operator> = line.split_whitespace().map(|word| {
-            match word {
-                "*" => Ok(Operator::Times),
-                "+" => Ok(Operator::Plus),
-                _ => Err(format!("Invalid operator '{word}'")),
-            }
-        }).collect::<Result<Vec<Operator>, String>>()?;
+        let operators = parse_operators(&line, n_rows + 1)?;
 
-        let mat = Array2::from_shape_vec((n_rows, line.len()), lines).map_err(|_| format!("Invalid shape"))?;
+        let mat = Array2::from_shape_vec((n_rows, line.len()), lines).map_err(|_| {
+            Error::parse("Line lengths don't match operator row", "")
+        })?;
 
         let mut arguments: Vec<Vec<u64>> = Vec::new();
         let mut buf: Vec<u64> = Vec::new();
 
-        for col in mat.axis_iter(Axis(1)) {
+        for (j, col) in mat.axis_iter(Axis(1)).enumerate() {
             let v = col.to_vec();
             let col = v.as_slice().as_str().trim();
-            if col.len() == 0 {
-                arguments.push(mem::replace(&mut buf, Vec::new()));
+            if col.is_empty() {
+                arguments.push(mem::take(&mut buf));
             } else {
-                buf.push(col.parse().map_err(|_| format!("Invalid argument {col}"))?);
+                buf.push(col.parse().map_err(|_| Error::parse("Invalid argument", col).at_column(j + 1))?);
             }
         }
         arguments.push(buf);
@@ -123,19 +127,28 @@ impl Problem {
     }
 }
 
-pub fn run(test: bool) -> Result<(), String> {
-    let verbosity = verbosity();
+#[derive(Debug, Clone, clap::Args)]
+pub struct Day6;
 
-    let mut input_path = input_dir().to_owned();
-    input_path.push(if test { "day6_test.txt" } else { "day6.txt" });
+impl Solution for Day6 {
+    const DAY: u8 = 6;
 
-    let mut file = File::open(input_path).expect("Failed to open input file");
+    /// Worksheet read row-wise (part 1) and column-wise (part 2)
+    type Input = (Problem, Problem);
+    type Part1 = u64;
+    type Part2 = u64;
 
-    let problem = Problem::parse_part1(BufReader::new(&mut file))?;
-    println!("Grand total (Part 1): {}", problem.solve(verbosity));
+    fn parse<R: BufRead>(&self, mut input: R) -> Result<Self::Input> {
+        let mut buf = Vec::new();
+        input.read_to_end(&mut buf)?;
+        Ok((Problem::parse_part1(buf.as_slice())?, Problem::parse_part2(buf.as_slice())?))
+    }
+
+    fn part1(&self, input: &Self::Input) -> Result<u64> {
+        Ok(input.0.solve(verbosity()))
+    }
 
-    file.seek(SeekFrom::Start(0)).unwrap();
-    let problem = Problem::parse_part2(BufReader::new(file))?;
-    println!("Grand total (Part 2): {}", problem.solve(verbosity));
-    Ok(())
-}
\ No newline at end of file
+    fn part2(&self, input: &Self::Input) -> Result<u64> {
+        Ok(input.1.solve(verbosity()))
+    }
+}
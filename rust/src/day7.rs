@@ -1,23 +1,27 @@
-use std::{fs::File, io::{BufRead, BufReader}};
+use std::io::BufRead;
 
-use super::input_dir;
+use super::{Error, Result, Solution};
 
-pub fn parse_manifold<R: BufRead>(mut file: R) -> Result<(Vec<Vec<bool>>, usize), String> {
+pub fn parse_manifold<R: BufRead>(mut file: R) -> Result<(Vec<Vec<bool>>, usize)> {
     let mut line = String::new();
 
-    file.read_line(&mut line).map_err(|e| format!("Error reading file: {e}"))?;
-    let start_idx = line.find('S').ok_or_else(|| format!("Can't find start position"))?;
+    file.read_line(&mut line)?;
+    let start_idx = line.find('S').ok_or_else(|| Error::parse("Can't find start position", line.trim_end()).at_line(1))?;
 
     let mut manifold = Vec::new();
 
     loop {
         line.clear();
-        file.read_line(&mut line).map_err(|e| format!("Error reading file: {e}"))?;
-        if line.len() == 0 { break; }
+        file.read_line(&mut line)?;
+        if line.is_empty() { break; }
 
         manifold.push(line.chars().map(|c| c == '^').collect());
     }
 
+    if manifold.is_empty() {
+        return Err(Error::parse("Manifold has no rows after start position", "").at_line(2));
+    }
+
     Ok((manifold, start_idx))
 }
 
@@ -48,16 +52,26 @@ pub fn run_manifold(manifold: &[Vec<bool>], start_idx: usize) -> (u64, u64) {
 }
 
 
-pub fn run(test: bool) -> Result<(), String> {
-    let mut input_path = input_dir().to_owned();
-    input_path.push(if test { "day7_test.txt" } else { "day7.txt" });
+#[derive(Debug, Clone, clap::Args)]
+pub struct Day7;
+
+impl Solution for Day7 {
+    const DAY: u8 = 7;
+
+    /// Splitter grid and start column
+    type Input = (Vec<Vec<bool>>, usize);
+    type Part1 = u64;
+    type Part2 = u64;
 
-    let file = File::open(input_path).expect("Failed to open input file");
-    let (manifold, start_idx) = parse_manifold(BufReader::new(file))?;
+    fn parse<R: BufRead>(&self, input: R) -> Result<Self::Input> {
+        parse_manifold(input)
+    }
 
-    let (n_splits, n_timelines) = run_manifold(&manifold, start_idx);
-    println!("# splits: {n_splits}");
-    println!("# timelines: {n_timelines}");
+    fn part1(&self, (splitters, start_idx): &Self::Input) -> Result<u64> {
+        Ok(run_manifold(splitters, *start_idx).0)
+    }
 
-    Ok(())
-}
\ No newline at end of file
+    fn part2(&self, (splitters, start_idx): &Self::Input) -> Result<u64> {
+        Ok(run_manifold(splitters, *start_idx).1)
+    }
+}
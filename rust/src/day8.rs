@@ -1,93 +1,216 @@
-use std::{collections::HashMap, fs::File, hash::Hash, io::{BufRead, BufReader}};
+use std::{collections::HashMap, hash::Hash, io::BufRead};
 
 use itertools::Itertools;
 use petgraph::unionfind::UnionFind;
+use toml::{Table, Value};
 
-use super::{input_dir, verbosity};
+use super::{kdtree::KdTree, point::{parse_points, Metric, Point}, verbosity, Error, Result, Solution};
 
 
-fn parse_coords<R: BufRead>(file: R) -> Result<Vec<[u64; 3]>, String> {
-    let mut coords = Vec::new();
+pub fn count_occurrences<T: Eq + Hash, I: IntoIterator<Item = T>>(iter: I) -> Vec<u32> {
+    let mut components: HashMap<T, u32> = HashMap::new();
+    for label in iter.into_iter() {
+        *components.entry(label).or_default() += 1;
+    }
+    let mut components: Vec<u32> = components.values().copied().collect();
+    components.sort_by(|a, b| b.cmp(a));
+    components
+}
 
-    for line in file.lines() {
-        let line = line.map_err(|e| format!("Error reading file: {e}"))?;
 
-        coords.push(line.splitn(3, ',').map(|w| w.parse::<u64>().ok())
-            .collect::<Option<Vec<u64>>>()
-            .and_then(|coord| coord.as_array().map(|arr| arr.to_owned()))
-            .ok_or_else(|| format!("Invalid coordinate: {line}"))?
-        );
+/// Sizes of the circuits formed by connecting the `connections` closest pairs, largest first
+pub fn circuit_sizes<const N: usize>(coords: &[Point<N>], metric: Metric, connections: usize) -> Vec<u32> {
+    let mut union_find: UnionFind<u32> = UnionFind::new(coords.len());
+    for (i, j) in KdTree::new(coords, metric).closest_pairs().take(connections) {
+        union_find.union(i, j);
     }
-
-    Ok(coords)
+    count_occurrences(union_find.into_labeling())
 }
 
 
-pub fn sqdist(coord1: &[u64; 3], coord2: &[u64; 3]) -> u64 {
-    coord1.iter().zip(coord2).map(|(&l, &r)| l.abs_diff(r).pow(2)).sum()
+/// Joining of two circuits by an edge of the minimum spanning tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Merge {
+    /// Junction boxes connected by the edge
+    pub edge: (u32, u32),
+    /// Length of the edge in the dendrogram's metric
+    pub dist: u64,
+    /// Circuits joined. Ids below the number of boxes are single boxes, and id `n + k` is the circuit
+    /// formed by merge `k` (as in scipy's linkage matrices).
+    pub circuits: (u32, u32),
+    /// Number of boxes in the new circuit
+    pub size: u32,
 }
 
+/// Single-linkage clustering of junction boxes: the minimum spanning tree edges in increasing
+/// order, each merging two circuits.
+#[derive(Debug, Clone)]
+pub struct Dendrogram {
+    n: usize,
+    merges: Vec<Merge>,
+}
 
-fn count_occurrences<T: Eq + Hash, I: IntoIterator<Item = T>>(iter: I) -> Vec<u32> {
-    let mut components: HashMap<T, u32> = HashMap::new();
-    for label in iter.into_iter() {
-        *components.entry(label).or_default() += 1;
+impl Dendrogram {
+    pub fn new<const N: usize>(coords: &[Point<N>], metric: Metric) -> Self {
+        let n = coords.len();
+        let mut union_find: UnionFind<u32> = UnionFind::new(n);
+        // circuit id and size of each union-find root
+        let mut circuits: Vec<(u32, u32)> = (0..n as u32).map(|i| (i, 1)).collect();
+        let mut merges = Vec::with_capacity(n.saturating_sub(1));
+
+        for (dist, i, j) in KdTree::new(coords, metric).minimum_spanning_tree() {
+            let (root_i, root_j) = (union_find.find_mut(i), union_find.find_mut(j));
+            union_find.union(root_i, root_j);
+
+            let ((left, left_size), (right, right_size)) = (circuits[root_i as usize], circuits[root_j as usize]);
+            let size = left_size + right_size;
+            merges.push(Merge { edge: (i, j), dist, circuits: (left, right), size });
+            circuits[union_find.find_mut(i) as usize] = ((n + merges.len() - 1) as u32, size);
+        }
+        Self { n, merges }
+    }
+
+    /// Merges in increasing order of distance. Edges form the minimum spanning tree.
+    pub fn merges(&self) -> &[Merge] {
+        &self.merges
+    }
+
+    /// Circuit label of each box after applying the first `merges` merges
+    pub fn cut(&self, merges: usize) -> Vec<u32> {
+        let mut union_find: UnionFind<u32> = UnionFind::new(self.n);
+        for merge in &self.merges[..merges.min(self.merges.len())] {
+            union_find.union(merge.edge.0, merge.edge.1);
+        }
+        union_find.into_labeling()
+    }
+
+    /// Circuit labels when split into `count` circuits (or as few as possible)
+    pub fn cut_circuits(&self, count: usize) -> Vec<u32> {
+        self.cut(self.n.saturating_sub(count.max(1)))
+    }
+
+    /// Circuit labels when only connecting boxes at most `dist` apart
+    pub fn cut_distance(&self, dist: u64) -> Vec<u32> {
+        self.cut(self.merges.partition_point(|merge| merge.dist <= dist))
     }
-    let mut components: Vec<u32> = components.values().copied().collect();
-    components.sort_by(|a, b| b.cmp(a));
-    components
 }
 
 
-pub fn run(test: bool) -> Result<(), String> {
-    let verbosity = verbosity();
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct Day8 {
+    /// Number of closest pairs to connect in part 1 (default: `connections` in answers.toml)
+    #[arg(long, value_name = "N")]
+    pub connections: Option<usize>,
+
+    /// Distance between junction boxes
+    #[arg(long, value_enum, default_value_t)]
+    pub metric: Metric,
+}
 
-    let mut input_path = input_dir().to_owned();
-    input_path.push(if test { "day8_test.txt" } else { "day8.txt" });
+impl Solution for Day8 {
+    const DAY: u8 = 8;
 
-    let file = File::open(input_path).expect("Failed to open input file");
-    let coords = parse_coords(BufReader::new(file))?;
+    type Input = Vec<Point<3>>;
+    type Part1 = u64;
+    type Part2 = i64;
 
-    if verbosity > 0 {
-        println!("coords:");
-        for coord in coords.iter() {
-            println!("{:3}", coord.iter().format(", "));
+    fn configure(&mut self, params: &Table) -> Result<()> {
+        if self.connections.is_none() {
+            self.connections = match params.get("connections") {
+                None => None,
+                Some(&Value::Integer(n)) if n >= 0 => Some(n as usize),
+                Some(v) => return Err(Error::parse("Invalid connection count", v.to_string())),
+            };
         }
+        Ok(())
     }
 
-    let mut pairs: Vec<(u32, u32)> = Vec::with_capacity(coords.len() * (coords.len() - 1) / 2);
+    fn parse<R: BufRead>(&self, input: R) -> Result<Self::Input> {
+        let coords = parse_points(input)?;
 
-    for i in 0..coords.len() {
-        for j in i+1..coords.len() {
-            pairs.push((i as u32, j as u32));
+        if verbosity() > 0 {
+            println!("coords:");
+            for coord in coords.iter() {
+                println!("{:3}", coord.iter().format(", "));
+            }
         }
+        Ok(coords)
     }
-    pairs.sort_by_cached_key(|&(i, j)| sqdist(&coords[i as usize], &coords[j as usize]));
-
-    let mut union_find: UnionFind<u32> = UnionFind::new(coords.len());
 
-    let (part1_pairs, part2_pairs) = pairs.split_at(if test { 10 } else { 1000 });
+    fn part1(&self, coords: &Self::Input) -> Result<u64> {
+        let connections = self.connections.ok_or_else(|| clap::Error::raw(
+            clap::error::ErrorKind::MissingRequiredArgument,
+            "No connection count for this input, pass --connections",
+        ))?;
+        let components = circuit_sizes(coords, self.metric, connections);
 
-    for &(i, j) in part1_pairs.iter() {
-        union_find.union(i, j);
+        if verbosity() > 0 {
+            println!("Part 1 sizes: {:?}", components);
+        }
+        components.iter().take(3).try_fold(1u64, |product, &size| product.checked_mul(size.into()))
+            .ok_or_else(|| Error::Unsolvable("Product of circuit sizes overflows u64".to_owned()))
     }
-    let components = count_occurrences(union_find.clone().into_labeling());
 
-    println!("Part 1 sizes: {:?}", components);
-    println!("Part 1 product: {}", components[..3].iter().product::<u32>());
+    fn part2(&self, coords: &Self::Input) -> Result<i64> {
+        let dendrogram = Dendrogram::new(coords, self.metric);
 
-    let mut n_components = components.len();
-
-    for &(i, j) in part2_pairs.iter() {
-        if union_find.union(i, j) {
-            n_components -= 1;
-            if n_components == 1 {
-                let product = coords[i as usize][0] * coords[j as usize][0];
-                println!("Part 2 product: {product}");
-                break
+        if verbosity() > 1 {
+            println!("Merges:");
+            for merge in dendrogram.merges() {
+                println!("  {:?} at {}: circuits {:?} -> size {}", merge.edge, merge.dist, merge.circuits, merge.size);
             }
         }
+        match dendrogram.merges().last() {
+            Some(&Merge { edge: (i, j), .. }) if coords.len() > 1 => coords[i as usize][0].checked_mul(coords[j as usize][0])
+                .ok_or_else(|| Error::Unsolvable("Product of x coordinates overflows i64".to_owned())),
+            _ => Err(Error::Unsolvable("Junction boxes never form a single circuit".to_owned())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COORDS: [Point<3>; 5] = [[0, 0, 0], [10, 0, 0], [1, 0, 0], [10, 2, 0], [30, 0, 0]];
+
+    #[test]
+    fn dendrogram_merges() {
+        let dendrogram = Dendrogram::new(&COORDS, Metric::SqEuclidean);
+        let merges: Vec<_> = dendrogram.merges().iter().map(|m| (m.edge, m.dist, m.circuits, m.size)).collect();
+        assert_eq!(merges, vec![
+            ((0, 2), 1, (0, 2), 2),
+            ((1, 3), 4, (1, 3), 2),
+            ((1, 2), 81, (6, 5), 4),
+            ((1, 4), 400, (7, 4), 5),
+        ]);
     }
 
-    Ok(())
-}
\ No newline at end of file
+    #[test]
+    fn dendrogram_cuts() {
+        let dendrogram = Dendrogram::new(&COORDS, Metric::SqEuclidean);
+        let sizes = |labels: Vec<u32>| count_occurrences(labels);
+        assert_eq!(sizes(dendrogram.cut_circuits(5)), vec![1; 5]);
+        assert_eq!(sizes(dendrogram.cut_circuits(3)), vec![2, 2, 1]);
+        assert_eq!(sizes(dendrogram.cut_circuits(1)), vec![5]);
+        assert_eq!(sizes(dendrogram.cut_distance(3)), vec![2, 1, 1, 1]);
+        assert_eq!(sizes(dendrogram.cut_distance(81)), vec![4, 1]);
+        assert_eq!(sizes(dendrogram.cut_distance(u64::MAX)), vec![5]);
+    }
+
+    #[test]
+    fn part2_far_apart_clusters() {
+        // Kruskal over the closest pairs would drain every pair within each cluster first
+        let coords: Vec<Point<3>> = (0..2).flat_map(|c| (0..5000).map(move |i| [c * 1_000_000 + i % 100, i / 100, 0])).collect();
+        let day8 = Day8 { connections: None, metric: Metric::SqEuclidean };
+        assert_eq!(day8.part2(&coords).unwrap(), 99 * 1_000_000);
+    }
+
+    #[test]
+    fn part1_beyond_u32() {
+        // three far apart lines of 2000 boxes, each a circuit once its neighbours are connected
+        let coords: Vec<Point<3>> = (0..3).flat_map(|line| (0..2000).map(move |x| [x, line * 1_000_000, 0])).collect();
+        let day8 = Day8 { connections: Some(3 * 1999), metric: Metric::SqEuclidean };
+        assert_eq!(day8.part1(&coords).unwrap(), 8_000_000_000);
+    }
+}
@@ -1,177 +1,765 @@
-use std::{collections::HashMap, fmt, fs::File, io::{BufRead, BufReader}};
+use std::{collections::{BinaryHeap, HashMap}, fmt, io::BufRead, ops::Range, path::PathBuf};
 
 use itertools::Itertools;
 use ndarray::Array2;
 
-use super::{input_dir, verbosity};
+use super::{point::{bounding_box, parse_point_line, Point}, polygon::{self, FillRule, Winding}, render::{self, Rgb, Scene}, verbosity, Error, Result, Solution};
 
+const PART1_COLOUR: Rgb = [31, 119, 180];
+const PART2_COLOUR: Rgb = [214, 39, 40];
 
-fn parse_tiles<R: BufRead>(file: R) -> Result<Vec<[u64; 2]>, String> {
-    let mut coords = Vec::new();
 
-    for line in file.lines() {
-        let line = line.map_err(|e| format!("Error reading file: {e}"))?;
+/// Area (or volume) of the box with opposite corner tiles `coord1` and `coord2`, including both
+pub fn square_area<const N: usize>(coord1: &Point<N>, coord2: &Point<N>) -> u64 {
+    // saturate rather than overflow for boxes spanning (almost) the whole coordinate range
+    coord1.iter().zip(coord2).map(|(&l, &r)| l.abs_diff(r).saturating_add(1)).fold(1, u64::saturating_mul)
+}
+
 
-        coords.push(line.splitn(2, ',').map(|w| w.parse::<u64>().ok())
-            .collect::<Option<Vec<u64>>>()
-            .and_then(|coord| coord.as_array().map(|arr| arr.to_owned()))
-            .ok_or_else(|| format!("Invalid coordinate: {line}"))?
-        );
+/// Rectangle with opposite corners at two red tiles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    /// Indices of the corner tiles
+    pub corners: (usize, usize),
+    pub area: u64,
+}
+
+impl Rect {
+    fn new(tiles: &[Point<2>], i: usize, j: usize) -> Self {
+        Self { corners: (i.min(j), i.max(j)), area: square_area(&tiles[i], &tiles[j]) }
     }
 
-    Ok(coords)
+    pub fn corner_tiles(&self, tiles: &[Point<2>]) -> [Point<2>; 2] {
+        [tiles[self.corners.0], tiles[self.corners.1]]
+    }
 }
 
+/// Largest rectangle with red tile corners inside the loop rasterised in `grid`
+pub fn largest_inside(tiles: &[Point<2>], grid: &PolyGrid) -> Option<Rect> {
+    let compressed: Vec<[usize; 2]> = tiles.iter().map(|tile| grid.compress_point(tile)).collect();
+    let mut best: Option<Rect> = None;
+    for i in 0..tiles.len() {
+        for j in i+1..tiles.len() {
+            let area = square_area(&tiles[i], &tiles[j]);
+            if best.is_some_and(|best| area <= best.area) { continue; }
 
-pub fn square_area(coord1: &[u64; 2], coord2: &[u64; 2]) -> u64 {
-    coord1.iter().zip(coord2).map(|(&l, &r)| l.abs_diff(r) + 1).product()
+            if grid.rect_inside(compressed[i], compressed[j]) {
+                best = Some(Rect::new(tiles, i, j));
+            }
+        }
+    }
+    best
 }
 
+/// Indices of the tiles with fewer than `layers` other tiles below and to the left of them
+/// (inclusive), by peeling off staircases of minimal tiles. Points are ordered by x.
+fn lower_layers(tiles: &[Point<2>], layers: usize) -> Vec<usize> {
+    let mut remaining: Vec<usize> = (0..tiles.len()).collect();
+    remaining.sort_by_key(|&i| (tiles[i][0], tiles[i][1]));
+
+    let mut layer_tiles = Vec::new();
+    for _ in 0..layers {
+        if remaining.is_empty() { break }
+        let mut min_y = i64::MAX;
+        remaining.retain(|&i| {
+            let minimal = tiles[i][1] < min_y;
+            if minimal {
+                min_y = tiles[i][1];
+                layer_tiles.push(i);
+            }
+            !minimal
+        });
+    }
+    layer_tiles.sort_by_key(|&i| (tiles[i][0], std::cmp::Reverse(tiles[i][1])));
+    layer_tiles
+}
 
-struct PolyGrid {
-    x_map: HashMap<u64, usize>,
-    y_map: HashMap<u64, usize>,
-    grid: Array2<bool>,
+/// Area of the rectangle with lower left corner `lower` and upper right corner `upper`, which is
+/// negative if they're the wrong way round on one axis and `i128::MIN` if on both
+fn oriented_area(lower: &Point<2>, upper: &Point<2>) -> i128 {
+    let dx = upper[0] as i128 - lower[0] as i128 + 1;
+    let dy = upper[1] as i128 - lower[1] as i128 + 1;
+    if dx <= 0 && dy <= 0 { i128::MIN } else { dx * dy }
 }
 
-impl PolyGrid {
-    pub fn new(x_map: HashMap<u64, usize>, y_map: HashMap<u64, usize>) -> Self {
-        let size = [y_map.len(), x_map.len()];
-        Self {
-            x_map, y_map,
-            grid: Array2::from_elem(size, false),
+/// Best upper right corner for each lower left corner, by divide and conquer. Both staircases are
+/// sorted by increasing x (and so decreasing y), which makes the best upper index non-decreasing.
+fn staircase_max(tiles: &[Point<2>], lower: &[usize], upper: &[usize], best: &mut (i128, usize, usize)) {
+    fn solve(tiles: &[Point<2>], lower: &[usize], upper: &[usize], best: &mut (i128, usize, usize)) {
+        if lower.is_empty() || upper.is_empty() { return }
+        let mid = lower.len() / 2;
+        let (opt, area) = upper.iter().enumerate()
+            .map(|(j, &u)| (j, oriented_area(&tiles[lower[mid]], &tiles[u])))
+            .max_by_key(|&(j, area)| (area, std::cmp::Reverse(j)))
+            .unwrap();
+        if area > best.0 { *best = (area, lower[mid], upper[opt]); }
+
+        solve(tiles, &lower[..mid], &upper[..=opt], best);
+        solve(tiles, &lower[mid + 1..], &upper[opt..], best);
+    }
+    solve(tiles, lower, upper, best)
+}
+
+/// Mirror tiles along the x and/or y axis, by `v -> -1 - v` so the whole range of `i64` reverses
+/// exactly and distances between tiles are unchanged
+fn flip(tiles: &[Point<2>], x: bool, y: bool) -> Vec<Point<2>> {
+    let flip = |v: i64, f: bool| if f { !v } else { v };
+    tiles.iter().map(|&[tx, ty]| [flip(tx, x), flip(ty, y)]).collect()
+}
+
+/// Largest rectangle with red tile corners, in O(n log n).
+///
+/// The lower left corner of the best rectangle can't have another tile below and to its left,
+/// and likewise for the upper right, so only the two staircases of extreme tiles are searched
+/// (and again with y flipped, for upper left and lower right corners).
+pub fn largest_rectangle(tiles: &[Point<2>]) -> Option<Rect> {
+    if tiles.len() < 2 { return None }
+
+    let mut best = (i128::MIN, 0, 0);
+    for flip_y in [false, true] {
+        let flipped = flip(tiles, false, flip_y);
+        let lower = lower_layers(&flipped, 1);
+        let mut upper = lower_layers(&flip(&flipped, true, true), 1);
+        upper.reverse();
+        staircase_max(&flipped, &lower, &upper, &mut best);
+    }
+    let (_, i, j) = best;
+    // a single tile can be on both staircases, but any two distinct tiles are at least as good
+    if i == j { return Some(Rect::new(tiles, 0, 1)) }
+    Some(Rect::new(tiles, i, j))
+}
+
+/// Contents of a range of corner tiles
+#[derive(Debug, Clone, Copy)]
+enum Split {
+    /// Indices of the two halves
+    Halves(usize, usize),
+    /// Index of the only tile
+    Tile(usize),
+}
+
+/// Corner tiles sorted by x, split recursively in half, with the bounding box of each range
+struct CornerRanges {
+    ranges: Vec<([Point<2>; 2], Split)>,
+}
+
+impl CornerRanges {
+    fn new(tiles: &[Point<2>], corners: &[usize]) -> Self {
+        fn build(tiles: &[Point<2>], corners: &[usize], ranges: &mut Vec<([Point<2>; 2], Split)>) -> usize {
+            let (bounds, split) = if let [i] = corners {
+                ([tiles[*i]; 2], Split::Tile(*i))
+            } else {
+                let (left, right) = corners.split_at(corners.len() / 2);
+                let (left, right) = (build(tiles, left, ranges), build(tiles, right, ranges));
+                let ([min1, max1], [min2, max2]) = (ranges[left].0, ranges[right].0);
+                ([[min1[0].min(min2[0]), min1[1].min(min2[1])], [max1[0].max(max2[0]), max1[1].max(max2[1])]], Split::Halves(left, right))
+            };
+            ranges.push((bounds, split));
+            ranges.len() - 1
         }
+        let mut ranges = Vec::with_capacity(2 * corners.len());
+        if !corners.is_empty() { build(tiles, corners, &mut ranges); }
+        Self { ranges }
+    }
+
+    /// Index of the range of all the corners
+    fn root(&self) -> Option<usize> {
+        self.ranges.len().checked_sub(1)
     }
+}
+
+/// The `k` largest rectangles with red tile corners, largest first (ties broken by corners).
+///
+/// A corner with `k` other tiles beyond it on both axes gives `k` rectangles at least as large by
+/// swapping it out, so only the first `k` staircase layers are candidates for corners. Pairs of
+/// lower left and upper right candidates are searched best first, splitting ranges of them
+/// sorted by x, where the rectangle spanning both ranges' bounding boxes bounds any pair's area.
+pub fn largest_rectangles(tiles: &[Point<2>], k: usize) -> Vec<Rect> {
+    // ranges of lower left and upper right corners, with y flipped or not
+    let mut sides = Vec::new();
+    for flip_y in [false, true] {
+        let flipped = flip(tiles, false, flip_y);
+        let lower = lower_layers(&flipped, k);
+        let mut upper = lower_layers(&flip(&flipped, true, true), k);
+        upper.reverse();
+        let (lower, upper) = (CornerRanges::new(&flipped, &lower), CornerRanges::new(&flipped, &upper));
+        sides.push((lower, upper));
+    }
+
+    // bound on the area, then ranges before single pairs with the same bound, then corners
+    type Entry = ((i128, bool, std::cmp::Reverse<(usize, usize)>), usize, usize, usize);
+    let entry = |side: usize, l: usize, u: usize| -> Option<Entry> {
+        let (lower, upper) = &sides[side];
+        let ((bounds_l, split_l), (bounds_u, split_u)) = (lower.ranges[l], upper.ranges[u]);
+        let area = oriented_area(&bounds_l[0], &bounds_u[1]);
+        let key = match (split_l, split_u) {
+            (Split::Tile(i), Split::Tile(j)) if i == j => return None,
+            (Split::Tile(i), Split::Tile(j)) => (area, false, std::cmp::Reverse((i.min(j), i.max(j)))),
+            _ => (area, true, std::cmp::Reverse((0, 0))),
+        };
+        // only pairs with the upper corner above and right of the lower one are rectangles
+        (area > 0).then_some((key, side, l, u))
+    };
+    let mut heap: BinaryHeap<Entry> = (0..2)
+        .filter_map(|side| entry(side, sides[side].0.root()?, sides[side].1.root()?))
+        .collect();
+
+    let mut rects: Vec<Rect> = Vec::new();
+    while rects.len() < k {
+        let Some((key, side, l, u)) = heap.pop() else { break };
+        let (lower, upper) = &sides[side];
+        let (split_l, split_u) = (lower.ranges[l].1, upper.ranges[u].1);
+        let children = match (split_l, split_u) {
+            (Split::Tile(_), Split::Tile(_)) => {
+                // a pair in line on an axis is a rectangle with y flipped or not, popped in a row
+                let rect = Rect::new(tiles, key.2.0.0, key.2.0.1);
+                if rects.last() != Some(&rect) { rects.push(rect); }
+                continue;
+            }
+            (Split::Halves(l1, l2), Split::Tile(_)) => [(l1, u), (l2, u)],
+            (Split::Tile(_), Split::Halves(u1, u2)) => [(l, u1), (l, u2)],
+            // split the larger range; ranges are numbered in post-order, so a range's index is
+            // further past its first half's the more corners it has
+            (Split::Halves(l1, l2), Split::Halves(u1, u2)) => if l - l1 >= u - u1 { [(l1, u), (l2, u)] } else { [(l, u1), (l, u2)] },
+        };
+        heap.extend(children.into_iter().filter_map(|(l, u)| entry(side, l, u)));
+    }
+    rects
+}
 
-    pub fn compress_point(&self, pt: &[u64; 2]) -> [usize; 2] {
+
+/// Red tiles, as one or more loops separated by blank lines in the input
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tiles {
+    /// All tiles, loop by loop
+    pub points: Vec<Point<2>>,
+    /// Range of `points` in each loop, and the input line of its first tile
+    loops: Vec<(Range<usize>, usize)>,
+}
+
+impl Tiles {
+    /// A single loop
+    pub fn from_loop(points: Vec<Point<2>>) -> Self {
+        let loops = if points.is_empty() { vec![] } else { vec![(0..points.len(), 1)] };
+        Self { points, loops }
+    }
+
+    pub fn loops(&self) -> impl Iterator<Item = &[Point<2>]> {
+        self.loops.iter().map(|(range, _)| &self.points[range.clone()])
+    }
+
+    /// End the loop of tiles since the last one, which started on input line `first_line`
+    fn end_loop(&mut self, first_line: usize) {
+        let start = self.loops.last().map_or(0, |(range, _)| range.end);
+        self.loops.push((start..self.points.len(), first_line));
+    }
+
+    /// Validate each loop, with errors at the input line of the offending tile, giving the
+    /// direction of each loop
+    pub fn validate(&self) -> Result<Vec<Winding>> {
+        self.loops.iter().map(|(range, first_line)| polygon::validate(&self.points[range.clone()], *first_line)).collect()
+    }
+}
+
+/// Check the region rasterised in `grid` has as many tiles as its loops enclose by Pick's theorem,
+/// when that's their sum: for a single loop, or loops whose bounding boxes don't overlap under
+/// even-odd fill
+fn check_tile_count(tiles: &Tiles, fill: FillRule, grid: &PolyGrid) -> Result<()> {
+    let boxes: Vec<[Point<2>; 2]> = tiles.loops().filter_map(bounding_box).collect();
+    let apart = |[min1, max1]: &[Point<2>; 2], [min2, max2]: &[Point<2>; 2]| {
+        (0..2).any(|axis| max1[axis] < min2[axis] || max2[axis] < min1[axis])
+    };
+    let separate = boxes.len() == 1 || (fill == FillRule::EvenOdd && boxes.iter().tuple_combinations().all(|(a, b)| apart(a, b)));
+    if !separate { return Ok(()) }
+
+    let expected: u128 = tiles.loops().map(polygon::tile_count).sum();
+    if grid.tile_count() != expected {
+        return Err(Error::Solver(format!("Rasterised region has {} tiles, but its loops enclose {expected}", grid.tile_count())));
+    }
+    Ok(())
+}
+
+/// Parse one tile per line, starting a new loop after each blank line
+pub fn parse_tiles<R: BufRead>(input: R) -> Result<Tiles> {
+    let mut tiles = Tiles::default();
+    let mut start = None;
+    for (i, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            if let Some(first_line) = start.take() { tiles.end_loop(first_line); }
+            continue;
+        }
+        start.get_or_insert(i + 1);
+        tiles.points.push(parse_point_line(&line, i + 1)?);
+    }
+    if let Some(first_line) = start { tiles.end_loop(first_line); }
+    Ok(tiles)
+}
+
+/// Region enclosed by loops of tiles, rasterised onto a (possibly compressed) grid of tile coordinates
+pub struct PolyGrid {
+    x_map: HashMap<i64, usize>,
+    y_map: HashMap<i64, usize>,
+    /// Summed-area table of cells outside the region: `outside[[y, x]]` counts those in rows
+    /// `..y` and columns `..x`
+    outside: Array2<u32>,
+    /// Direction of each loop
+    windings: Vec<Winding>,
+}
+
+impl PolyGrid {
+    pub fn compress_point(&self, pt: &Point<2>) -> [usize; 2] {
         [*self.x_map.get(&pt[0]).expect("Missing point in x_map"), *self.y_map.get(&pt[1]).expect("Missing point in y_map")]
     }
 
-    pub fn make(poly: &[[u64; 2]], compress: bool) -> Self {
-        let mut grid = if compress {
-            Self::new(compress_points(poly, 0), compress_points(poly, 1))
+    /// Validate and rasterise the region enclosed by loops of tiles, under `fill`
+    pub fn make(tiles: &Tiles, fill: FillRule, compress: bool) -> Result<Self> {
+        let windings = tiles.validate()?;
+
+        let points = &tiles.points;
+        let (x_map, y_map) = if compress {
+            (compress_points(points, 0), compress_points(points, 1))
         } else {
-            Self::new(uncompress_points(poly, 0), uncompress_points(poly, 1))
+            (uncompress_points(points, 0), uncompress_points(points, 1))
         };
+        let compress_point = |pt: &Point<2>| [x_map[&pt[0]], y_map[&pt[1]]];
 
-        let mut compressed_poly: Vec<[usize; 2]> = poly.iter().map(|pt| grid.compress_point(pt)).collect();
-        // close polygon
-        compressed_poly.push(compressed_poly[0]);
+        let compressed_loops: Vec<Vec<[usize; 2]>> = tiles.loops().map(|poly| {
+            let mut compressed_poly: Vec<[usize; 2]> = poly.iter().map(compress_point).collect();
+            // close polygon
+            compressed_poly.push(compressed_poly[0]);
+            compressed_poly
+        }).collect();
 
-        grid.draw_poly(&compressed_poly);
-        grid
+        let grid = draw_poly(&compressed_loops, [y_map.len(), x_map.len()], fill);
+        Ok(Self { x_map, y_map, outside: summed_area(&grid), windings })
     }
 
-    fn draw_poly(&mut self, poly: &[[usize; 2]]) {
-        // even-odd fill vertical lines
-        for (start, end) in poly.iter().zip(&poly[1..]) {
-            if start[1] == end[1] { continue }
-            // for downwards lines, consider x outside. for upwards lines, consider x inside.
-            let start_x = if start[1] > end[1] { start[0] } else { start[0] + 1 };
-            let (start_y, end_y) = if start[1] > end[1] { (end[1], start[1]) } else { (start[1], end[1]) };
-            for y in start_y..end_y {
-                for x in start_x..self.grid.shape()[1] {
-                    self.grid[[y, x]] ^= true;
+    /// Direction of each loop, in input order
+    pub fn windings(&self) -> &[Winding] {
+        &self.windings
+    }
+
+    /// Number of grid cells outside the region in the rectangle between compressed corners `min` and `max` (inclusive)
+    fn count_outside(&self, min: [usize; 2], max: [usize; 2]) -> u32 {
+        let sat = &self.outside;
+        sat[[max[1] + 1, max[0] + 1]] + sat[[min[1], min[0]]] - sat[[min[1], max[0] + 1]] - sat[[max[1] + 1, min[0]]]
+    }
+
+    /// Whether grid cell `[x, y]` (compressed) is inside the region or on its boundary
+    pub fn inside(&self, cell: [usize; 2]) -> bool {
+        self.count_outside(cell, cell) == 0
+    }
+
+    /// Whether the rectangle with corners `coord1` and `coord2` lies within the region, in O(1)
+    pub fn part2_rect_valid(&self, coord1: &Point<2>, coord2: &Point<2>) -> bool {
+        self.rect_inside(self.compress_point(coord1), self.compress_point(coord2))
+    }
+
+    /// Whether the rectangle with compressed corners `[x1, y1]` and `[x2, y2]` lies within the region
+    pub fn rect_inside(&self, [x1, y1]: [usize; 2], [x2, y2]: [usize; 2]) -> bool {
+        self.count_outside([x1.min(x2), y1.min(y2)], [x1.max(x2), y1.max(y2)]) == 0
+    }
+
+    /// Coordinates of each grid column and row, followed by one more: a cell spans from its
+    /// coordinates up to (but not including) the next ones
+    fn coords(&self) -> [Vec<i64>; 2] {
+        [&self.x_map, &self.y_map].map(|map| {
+            // the last cell is a single tile
+            let mut coords = vec![0; map.len() + 1];
+            for (&v, &i) in map { coords[i] = v; }
+            coords[map.len()] = coords[map.len() - 1].saturating_add(1);
+            coords
+        })
+    }
+
+    /// Real tiles spanned by each cell inside the region, as ranges of x and y
+    fn inside_cells(&self) -> impl Iterator<Item = [Range<i64>; 2]> + '_ {
+        let [xs, ys] = self.coords();
+        (0..self.y_map.len()).flat_map(move |y| (0..self.x_map.len()).map(move |x| [x, y]))
+            .filter(|&cell| self.inside(cell))
+            .map(move |[x, y]| [xs[x]..xs[x + 1], ys[y]..ys[y + 1]])
+    }
+
+    /// Number of tiles inside the region or on its boundary
+    pub fn tile_count(&self) -> u128 {
+        self.inside_cells().map(|[xs, ys]| xs.end.abs_diff(xs.start) as u128 * ys.end.abs_diff(ys.start) as u128).sum()
+    }
+
+    /// Smallest box containing the region, as its top left and bottom right tiles
+    pub fn bounding_box(&self) -> Option<[Point<2>; 2]> {
+        let corners: Vec<Point<2>> = self.inside_cells().flat_map(|[xs, ys]| [[xs.start, ys.start], [xs.end - 1, ys.end - 1]]).collect();
+        bounding_box(&corners)
+    }
+
+    /// Largest rectangle inside the region with corners anywhere, as its top left and bottom
+    /// right tiles and its area.
+    ///
+    /// Each row of grid cells is the base of a histogram of how far cells extend upwards inside
+    /// the region, whose largest rectangle is found with a stack of bars of increasing height.
+    /// Cells span from their coordinate up to the next one, so widths and heights are real lengths.
+    pub fn largest_inscribed(&self) -> Option<([Point<2>; 2], u64)> {
+        let [xs, ys] = self.coords();
+        let (cols, rows) = (self.x_map.len(), self.y_map.len());
+
+        let mut best: Option<([Point<2>; 2], u64)> = None;
+        let mut heights = vec![0_u64; cols];
+        // first column and height of each bar
+        let mut stack: Vec<(usize, u64)> = Vec::with_capacity(cols);
+        for y in 0..rows {
+            let row_height = ys[y + 1].abs_diff(ys[y]);
+            for (x, height) in heights.iter_mut().enumerate() {
+                *height = if self.inside([x, y]) { height.saturating_add(row_height) } else { 0 };
+            }
+
+            stack.clear();
+            for x in 0..=cols {
+                let height = heights.get(x).copied().unwrap_or(0);
+                let mut start = x;
+                while let Some(&(first, bar)) = stack.last() {
+                    if bar < height { break }
+                    stack.pop();
+                    start = first;
+                    let area = xs[x].abs_diff(xs[first]).saturating_mul(bar);
+                    if bar > 0 && best.is_none_or(|(_, best)| area > best) {
+                        // bars end at the bottom of row y, and are never taller than the grid
+                        let top = ys[y + 1].wrapping_sub_unsigned(bar);
+                        best = Some(([[xs[first], top], [xs[x] - 1, ys[y + 1] - 1]], area));
+                    }
                 }
+                stack.push((start, height));
             }
         }
+        best
+    }
+}
 
-        // and then draw horizontal lines
+/// Rasterise closed polygons (first vertex repeated at the end) onto a grid of `shape` `[rows, columns]`,
+/// filling the cells on their boundaries and those inside under `fill`
+fn draw_poly(loops: &[Vec<[usize; 2]>], shape: [usize; 2], fill: FillRule) -> Array2<bool> {
+    let mut winding = Array2::<i32>::zeros(shape);
+    let width = shape[1];
+
+    // winding numbers of cells off the boundary: each vertical line changes them for the rest of
+    // the rows it spans (as if just below the row, so a row through a vertex is only counted once
+    // by the two lines meeting there), marked here at the first changed cell and then accumulated
+    // along each row. upwards lines count positive, so clockwise loops wind +1.
+    for poly in loops {
         for (start, end) in poly.iter().zip(&poly[1..]) {
-            if start[0] == end[0] { continue; }
-
-            let (start_x, end_x) = if start[0] > end[0] { (end[0], start[0]) } else { (start[0], end[0]) };
-            for x in start_x..=end_x {
-                self.grid[[start[1], x]] = true;
+            if start[1] == end[1] || start[0] + 1 >= width { continue }
+            let (start_y, end_y, sign) = if start[1] > end[1] { (end[1], start[1], 1) } else { (start[1], end[1], -1) };
+            for y in start_y..end_y {
+                winding[[y, start[0] + 1]] += sign;
             }
         }
     }
-
-    pub fn part2_rect_valid(&self, coord1: &[u64; 2], coord2: &[u64; 2]) -> bool {
-        let mut coord1 = self.compress_point(coord1);
-        let mut coord2 = self.compress_point(coord2);
-        if coord1[0] > coord2[0] { std::mem::swap(&mut coord1[0], &mut coord2[0]); }
-        if coord1[1] > coord2[1] { std::mem::swap(&mut coord1[1], &mut coord2[1]); }
-
-        // vertical segments
-        for x in &[coord1[0], coord2[0]] {
-            for y in coord1[1]..=coord2[1] { if !self.grid[[y, *x]] { return false; } }
+    for mut row in winding.rows_mut() {
+        let mut total = 0;
+        for cell in row.iter_mut() {
+            total += *cell;
+            *cell = total;
         }
+    }
+    let mut grid = winding.mapv(|w| fill.inside(w));
 
-        // horizontal segments
-        for y in &[coord1[1], coord2[1]] {
-            for x in coord1[0] + 1..coord2[0] { if !self.grid[[*y, x]] { return false; } }
+    // and then draw the lines themselves
+    for poly in loops {
+        for (start, end) in poly.iter().zip(&poly[1..]) {
+            for y in start[1].min(end[1])..=start[1].max(end[1]) {
+                for x in start[0].min(end[0])..=start[0].max(end[0]) {
+                    grid[[y, x]] = true;
+                }
+            }
         }
+    }
+    grid
+}
 
-        true
+/// Summed-area table of the cells outside the polygon, with an extra leading row and column of zeros
+fn summed_area(grid: &Array2<bool>) -> Array2<u32> {
+    let (rows, cols) = grid.dim();
+    let mut sat = Array2::zeros((rows + 1, cols + 1));
+    for y in 0..rows {
+        let mut row_sum = 0;
+        for x in 0..cols {
+            row_sum += u32::from(!grid[[y, x]]);
+            sat[[y + 1, x + 1]] = sat[[y, x + 1]] + row_sum;
+        }
     }
+    sat
 }
 
 impl fmt::Display for PolyGrid {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for line in self.grid.outer_iter() {
-            writeln!(f, "{}", line.iter().map(|&b| if b { "X" } else { "." }).collect::<String>())?;
+        for y in 0..self.y_map.len() {
+            writeln!(f, "{}", (0..self.x_map.len()).map(|x| if self.inside([x, y]) { "X" } else { "." }).collect::<String>())?;
         }
         Ok(())
     }
 }
 
-fn compress_points(points: &[[u64; 2]], idx: usize) -> HashMap<u64, usize> {
-    let mut indices = points.iter().map(|v| v[idx]).unique().collect_vec();
+/// Map coordinates to consecutive indices. Each coordinate is followed by the next one up, which
+/// stands for the gap to the next coordinate: a gap may be outside where both sides are inside.
+fn compress_points(points: &[Point<2>], idx: usize) -> HashMap<i64, usize> {
+    let mut indices = points.iter().flat_map(|v| [v[idx], v[idx].saturating_add(1)]).unique().collect_vec();
     indices.sort();
     indices.into_iter().enumerate().map(|(i, v)| (v, i)).collect()
 }
 
-fn uncompress_points(points: &[[u64; 2]], idx: usize) -> HashMap<u64, usize> {
+fn uncompress_points(points: &[Point<2>], idx: usize) -> HashMap<i64, usize> {
     let (min, max) = (points.iter().map(|v| v[idx]).min().unwrap(), points.iter().map(|v| v[idx]).max().unwrap());
-    (min-1..=max+1).into_iter().enumerate().map(|(i, v)| (v, i)).collect()
+    (min.saturating_sub(1)..=max.saturating_add(1)).enumerate().map(|(i, v)| (v, i)).collect()
 }
 
-pub fn run(test: bool, compress: bool) -> Result<(), String> {
-    let verbosity = verbosity();
+#[derive(Debug, Clone, clap::Args)]
+pub struct Day9 {
+    /// Don't compress the coordinate grid
+    #[arg(long = "no-compress", action = clap::ArgAction::SetFalse)]
+    pub compress: bool,
 
-    let mut input_path = input_dir().to_owned();
-    input_path.push(if test { "day9_test.txt" } else { "day9.txt" });
+    /// Which tiles nested or overlapping loops enclose
+    #[arg(long, value_enum, default_value_t)]
+    pub fill: FillRule,
 
-    let file = File::open(input_path).expect("Failed to open input file");
-    let tiles: Vec<[u64; 2]> = parse_tiles(BufReader::new(file))?;
+    /// Print the K largest rectangles for part 1
+    #[arg(long, value_name = "K")]
+    pub top: Option<usize>,
 
-    let mut max_area: u64 = 0;
+    /// Find the largest rectangle inside the loops for part 2 with corners anywhere, rather than on red tiles
+    #[arg(long)]
+    pub any_corners: bool,
 
-    // part 1
-    // this could be much better with some pruning
-    for i in 0..tiles.len() {
-        for j in i+1..tiles.len() {
-            let area = square_area(&tiles[i], &tiles[j]);
-            max_area = max_area.max(area);
+    /// Draw the loop and the largest rectangles for both parts to an SVG, PPM or PNG image
+    #[arg(long, value_name = "PATH", value_parser = render::parse_image_path)]
+    pub render: Option<PathBuf>,
+}
+
+impl Default for Day9 {
+    /// The same options as on the command line, with a compressed grid
+    fn default() -> Self {
+        Self { compress: true, fill: FillRule::default(), top: None, any_corners: false, render: None }
+    }
+}
 
-            if verbosity > 1 {
-                println!("{:?} - {:?} area: {area}", &tiles[i], &tiles[j])
+impl Solution for Day9 {
+    const DAY: u8 = 9;
+
+    type Input = Tiles;
+    type Part1 = u64;
+    type Part2 = u64;
+
+    fn parse<R: BufRead>(&self, input: R) -> Result<Self::Input> {
+        let tiles = parse_tiles(input)?;
+        tiles.validate()?;
+        Ok(tiles)
+    }
+
+    fn part1(&self, tiles: &Self::Input) -> Result<u64> {
+        let tiles = &tiles.points;
+        if let Some(k) = self.top {
+            println!("Largest rectangles:");
+            for rect in largest_rectangles(tiles, k) {
+                let (i, j) = rect.corners;
+                println!("  {:?} - {:?} area: {}", tiles[i], tiles[j], rect.area);
             }
         }
+        Ok(largest_rectangle(tiles).map_or(0, |rect| rect.area))
     }
-    println!("Part 1 largest area: {}", max_area);
 
-    // part 2
+    fn part2(&self, tiles: &Self::Input) -> Result<u64> {
+        if tiles.points.is_empty() {
+            return Err(Error::Unsolvable("No red tiles".to_owned()));
+        }
+        let grid = PolyGrid::make(tiles, self.fill, self.compress)?;
+        check_tile_count(tiles, self.fill, &grid)?;
+
+        if verbosity() > 0 {
+            for (i, (poly, winding)) in tiles.loops().zip(grid.windings()).enumerate() {
+                println!(
+                    "Loop {}: {winding}, {} tiles, {} on the boundary, bounds {:?}",
+                    i + 1, polygon::tile_count(poly), polygon::boundary_length(poly), bounding_box(poly).unwrap(),
+                );
+            }
+            println!("Region: {} tiles, bounds {:?}", grid.tile_count(), grid.bounding_box());
+        }
+        if verbosity() > 1 {
+            println!("Grid:\n{}", grid);
+        }
 
-    let grid = PolyGrid::make(&tiles, compress);
+        let points = &tiles.points;
+        let best = if self.any_corners {
+            let best = grid.largest_inscribed();
+            if let Some(([top_left, bottom_right], area)) = best {
+                println!("Largest rectangle: {top_left:?} - {bottom_right:?} area: {area}");
+            }
+            best
+        } else {
+            largest_inside(points, &grid).map(|rect| (rect.corner_tiles(points), rect.area))
+        };
 
-    if verbosity > 0 {
-        println!("Grid:\n{}", grid);
+        if let Some(path) = &self.render {
+            let part1 = largest_rectangle(points).map(|rect| (rect.corner_tiles(points), PART1_COLOUR));
+            let part2 = best.map(|(corners, _)| (corners, PART2_COLOUR));
+            Scene { loops: tiles.loops().collect(), fill: self.fill, rects: part1.into_iter().chain(part2).collect() }.write(path)?;
+        }
+        Ok(best.map_or(0, |(_, area)| area))
     }
+}
 
-    max_area = 0;
-    for i in 0..tiles.len() {
-        for j in i+1..tiles.len() {
-            let area = square_area(&tiles[i], &tiles[j]);
-            if area <= max_area { continue; }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{pairs, Rng};
 
-            if grid.part2_rect_valid(&tiles[i], &tiles[j]) {
-                max_area = area;
+    /// Deterministic pseudo-random tiles, with plenty of duplicate coordinates
+    fn tiles(n: usize, range: u64) -> Vec<Point<2>> {
+        Rng::new(0x9e3779b97f4a7c15).points(n, range)
+    }
+
+    fn all_rectangles(tiles: &[Point<2>]) -> Vec<u64> {
+        let mut areas: Vec<u64> = pairs(tiles.len()).map(|(i, j)| square_area(&tiles[i], &tiles[j])).collect();
+        areas.sort_by(|a, b| b.cmp(a));
+        areas
+    }
+
+    #[test]
+    fn largest_rectangle_matches_all_pairs() {
+        for (n, range) in [(0, 10), (1, 10), (2, 10), (3, 2), (40, 6), (200, 100), (500, 100000)] {
+            // also skewed, for long staircases in each direction
+            let skewed = |sign: i64| tiles(n, range).into_iter().map(|[x, y]| [x, sign * 4 * x + y]).collect();
+            // and against the lowest coordinates, which must still mirror exactly
+            let low = i64::MIN + (range / 2) as i64;
+            let edge = tiles(n, range).into_iter().map(|[x, y]| [x + low, y + low]).collect();
+            for tiles in [tiles(n, range), skewed(1), skewed(-1), edge] {
+                let areas = all_rectangles(&tiles);
+                let rect = largest_rectangle(&tiles);
+                assert_eq!(rect.map(|rect| rect.area), areas.first().copied(), "{n} tiles in 0..{range}");
+                if let Some(Rect { corners: (i, j), area }) = rect {
+                    assert!(i != j);
+                    assert_eq!(square_area(&tiles[i], &tiles[j]), area);
+                }
             }
         }
     }
-    println!("Part 2 largest area: {}", max_area);
 
-    Ok(())
-}
\ No newline at end of file
+    #[test]
+    fn largest_rectangles_top_k() {
+        for (n, range) in [(3, 2), (40, 6), (200, 100)] {
+            let tiles = tiles(n, range);
+            let areas = all_rectangles(&tiles);
+            for k in [1, 2, 5, 20] {
+                let rects = largest_rectangles(&tiles, k);
+                assert_eq!(rects.iter().map(|rect| rect.area).collect::<Vec<_>>(), areas[..k.min(areas.len())]);
+            }
+        }
+    }
+
+    #[test]
+    fn largest_rectangles_on_a_circle() {
+        // every tile is on the outer staircases, so all pairs of them are candidates
+        let tiles: Vec<Point<2>> = (0..40_000).map(|i| {
+            let angle = i as f64 * std::f64::consts::TAU / 40_000.0;
+            [(1e9 * angle.cos()).round() as i64, (1e9 * angle.sin()).round() as i64]
+        }).collect();
+        let best = largest_rectangle(&tiles).unwrap();
+        assert_eq!(largest_rectangles(&tiles, 1), vec![best]);
+
+        let rects = largest_rectangles(&tiles, 50);
+        assert_eq!(rects.len(), 50);
+        assert_eq!(rects[0].area, best.area);
+        assert!(rects.windows(2).all(|pair| pair[0].area >= pair[1].area && pair[0].corners != pair[1].corners));
+        assert!(rects.iter().all(|rect| rect.area == square_area(&tiles[rect.corners.0], &tiles[rect.corners.1])));
+    }
+
+    /// Largest rectangle of cells inside an uncompressed grid, by trying them all
+    fn inscribed_brute_force(grid: &PolyGrid) -> u64 {
+        let (cols, rows) = (grid.x_map.len(), grid.y_map.len());
+        let mut best = 0;
+        for (x1, y1) in (0..cols).cartesian_product(0..rows) {
+            for (x2, y2) in (x1..cols).cartesian_product(y1..rows) {
+                if grid.rect_inside([x1, y1], [x2, y2]) {
+                    best = best.max(((x2 - x1 + 1) * (y2 - y1 + 1)) as u64);
+                }
+            }
+        }
+        best
+    }
+
+    #[test]
+    fn largest_inscribed_matches_brute_force() {
+        let example = "7,1\n11,1\n11,7\n9,7\n9,5\n2,5\n2,3\n7,3\n";
+        let holes = "0,0\n10,0\n10,10\n0,10\n\n3,3\n7,3\n7,7\n3,7\n\n-4,1\n-2,1\n-2,12\n-4,12\n";
+        for (input, expected) in [(example, 30), (holes, 44)] {
+            let tiles = parse_tiles(input.as_bytes()).unwrap();
+            let uncompressed = PolyGrid::make(&tiles, FillRule::EvenOdd, false).unwrap();
+            assert_eq!(inscribed_brute_force(&uncompressed), expected, "{input}");
+
+            for compress in [true, false] {
+                let grid = PolyGrid::make(&tiles, FillRule::EvenOdd, compress).unwrap();
+                let ([[x1, y1], [x2, y2]], area) = grid.largest_inscribed().unwrap();
+                assert_eq!(area, expected, "{input}");
+                assert_eq!(square_area(&[x1, y1], &[x2, y2]), area);
+            }
+        }
+    }
+
+    #[test]
+    fn region_measures() {
+        let example = parse_tiles("7,1\n11,1\n11,7\n9,7\n9,5\n2,5\n2,3\n7,3\n".as_bytes()).unwrap();
+        let holes = parse_tiles("0,0\n10,0\n10,10\n0,10\n\n3,3\n7,3\n7,7\n3,7\n".as_bytes()).unwrap();
+        for compress in [true, false] {
+            let grid = PolyGrid::make(&example, FillRule::EvenOdd, compress).unwrap();
+            assert_eq!(grid.tile_count(), polygon::tile_count(&example.points));
+            assert_eq!(grid.tile_count(), 46);
+            assert_eq!(grid.bounding_box(), Some([[2, 1], [11, 7]]));
+
+            // the hole's boundary is part of the region, but not its inside
+            let grid = PolyGrid::make(&holes, FillRule::EvenOdd, compress).unwrap();
+            assert_eq!(grid.tile_count(), 121 - 9);
+            assert_eq!(grid.bounding_box(), Some([[0, 0], [10, 10]]));
+            // nested loops aren't checked, but a single loop rasterised as anything else fails
+            assert!(check_tile_count(&holes, FillRule::EvenOdd, &grid).is_ok());
+            assert!(matches!(check_tile_count(&example, FillRule::EvenOdd, &grid), Err(Error::Solver(_))));
+
+            let apart = parse_tiles("0,0\n4,0\n4,4\n0,4\n\n6,0\n8,0\n8,2\n6,2\n".as_bytes()).unwrap();
+            let grid = PolyGrid::make(&apart, FillRule::EvenOdd, compress).unwrap();
+            assert_eq!(grid.tile_count(), 25 + 9);
+            assert!(check_tile_count(&apart, FillRule::EvenOdd, &grid).is_ok());
+        }
+    }
+
+    #[test]
+    fn parse_loops() {
+        let tiles = parse_tiles("0,0\n4,0\n4,4\n0,4\n\n\n1,1\n2,1\n3,2\n2,2\n\n".as_bytes()).unwrap();
+        assert_eq!(tiles.points.len(), 8);
+        assert_eq!(tiles.loops().map(<[_]>::len).collect::<Vec<_>>(), [4, 4]);
+        // the diagonal edge in the second loop
+        assert!(matches!(tiles.validate(), Err(Error::Parse { line: Some(8), .. })));
+
+        let tiles = parse_tiles("0,0\n4,0\n4,4\n0,4\n\n1,1\n2,1\n".as_bytes()).unwrap();
+        assert!(matches!(tiles.validate(), Err(Error::Parse { line: Some(6), .. })));
+        let tiles = parse_tiles("0,0\n4,0\n4,4\n0,4\n\n1,1\n1,3\n3,3\n3,1\n".as_bytes()).unwrap();
+        assert_eq!(tiles.validate().unwrap(), [Winding::Clockwise, Winding::CounterClockwise]);
+        // part 1 doesn't rasterise the loops, so they're checked as they're parsed
+        assert!(matches!(Day9::default().parse("0,0\n4,0\n3,4\n0,4\n".as_bytes()), Err(Error::Parse { line: Some(2), .. })));
+        assert_eq!(parse_tiles("".as_bytes()).unwrap(), Tiles::default());
+        assert_eq!(parse_tiles("1,2\n3,4\n".as_bytes()).unwrap(), Tiles::from_loop(vec![[1, 2], [3, 4]]));
+    }
+
+    #[test]
+    fn default_matches_command_line() {
+        use clap::{Args, Command, FromArgMatches};
+        let parsed = Day9::from_arg_matches(&Day9::augment_args(Command::new("day9")).get_matches_from(["day9"])).unwrap();
+        let default = Day9::default();
+        assert_eq!((parsed.compress, parsed.fill, parsed.top, parsed.any_corners, parsed.render), (default.compress, default.fill, default.top, default.any_corners, default.render));
+    }
+
+    #[test]
+    fn square_area_is_inclusive() {
+        assert_eq!(square_area(&[2, 5], &[9, 7]), 24);
+        assert_eq!(square_area(&[9, 7], &[2, 5]), 24);
+        assert_eq!(square_area(&[4, 4], &[4, 4]), 1);
+        assert_eq!(square_area(&[-2, 5], &[3, -1]), 42);
+        assert_eq!(square_area(&[i64::MIN, 0], &[i64::MAX, 1]), u64::MAX);
+    }
+}
@@ -0,0 +1,92 @@
+use std::{fmt, io};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Failed to read input
+    Io(io::Error),
+    /// Malformed input. `line` and `column` are 1-based.
+    Parse {
+        line: Option<usize>,
+        column: Option<usize>,
+        text: String,
+        message: String,
+    },
+    /// Input is well-formed but has no solution
+    Unsolvable(String),
+    /// An external solver failed or gave an unusable result
+    Solver(String),
+    /// Invalid command line arguments
+    Args(clap::Error),
+}
+
+impl Error {
+    pub fn parse(message: impl Into<String>, text: impl Into<String>) -> Self {
+        Error::Parse { line: None, column: None, text: text.into(), message: message.into() }
+    }
+
+    /// Set the line of a parse error. Other errors are returned unchanged.
+    pub fn at_line(mut self, n: usize) -> Self {
+        if let Error::Parse { line, .. } = &mut self { *line = Some(n); }
+        self
+    }
+
+    /// Set the column of a parse error. Other errors are returned unchanged.
+    pub fn at_column(mut self, n: usize) -> Self {
+        if let Error::Parse { column, .. } = &mut self { *column = Some(n); }
+        self
+    }
+
+    /// Set the column of a parse error from the position of `word` inside `line`.
+    pub fn at_word(self, line: &str, word: &str) -> Self {
+        match line.substr_range(word) {
+            Some(range) => self.at_column(range.start + 1),
+            None => self,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "Error reading input: {e}"),
+            Error::Parse { line, column, text, message } => {
+                match (line, column) {
+                    (Some(line), Some(column)) => write!(f, "line {line}, column {column}: ")?,
+                    (Some(line), None) => write!(f, "line {line}: ")?,
+                    (None, Some(column)) => write!(f, "column {column}: ")?,
+                    (None, None) => (),
+                }
+                write!(f, "{message}")?;
+                if !text.is_empty() { write!(f, ": '{text}'")?; }
+                Ok(())
+            },
+            Error::Unsolvable(message) => write!(f, "No solution: {message}"),
+            Error::Solver(message) => write!(f, "Solver failed: {message}"),
+            Error::Args(e) => {
+                // clap's rendering is meant to be printed on its own
+                let rendered = e.to_string();
+                write!(f, "{}", rendered.trim_end().trim_start_matches("error: "))
+            },
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Args(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self { Error::Io(value) }
+}
+
+impl From<clap::Error> for Error {
+    fn from(value: clap::Error) -> Self { Error::Args(value) }
+}
@@ -0,0 +1,333 @@
+use std::collections::{HashMap, VecDeque};
+
+use itertools::Itertools;
+
+/// Bits packed into words, least significant first
+type Row = Vec<u64>;
+
+fn get(row: &[u64], i: usize) -> bool {
+    row[i / 64] >> (i % 64) & 1 == 1
+}
+
+fn flip(row: &mut [u64], i: usize) {
+    row[i / 64] ^= 1 << (i % 64);
+}
+
+fn xor_into(dst: &mut [u64], src: &[u64]) {
+    for (d, s) in dst.iter_mut().zip(src) { *d ^= s; }
+}
+
+fn weight(row: &[u64]) -> u32 {
+    row.iter().map(|w| w.count_ones()).sum()
+}
+
+/// Most free or pivot variables [`Solutions::min_weight`] enumerates the combinations of
+pub const MAX_FREE: usize = 24;
+
+/// Most sets of columns [`Solutions::min_weight`] tries when there are too many free and pivot
+/// variables to enumerate
+pub const MAX_SETS: usize = 1 << 20;
+
+/// Certificate that `A x = b` has no solution: the sum of these rows of `A` is zero, but the sum
+/// of the same rows of `b` is one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inconsistent {
+    pub rows: Vec<usize>,
+}
+
+/// All solutions of a linear system `A x = b` over GF(2), as a particular solution plus any
+/// combination of a basis of the null space of `A`
+#[derive(Debug, Clone)]
+pub struct Solutions {
+    vars: usize,
+    /// Solution with all free variables zero
+    particular: Row,
+    /// One vector per free variable
+    null_space: Vec<Row>,
+    /// Variable of each row of the reduced system `R x = c`
+    pivots: Vec<usize>,
+    /// Free variables, in the order of `null_space`
+    free_vars: Vec<usize>,
+    /// Each column of `R`, with a bit per pivot
+    reduced: Vec<Row>,
+    /// `c`, with a bit per pivot
+    reduced_target: Row,
+}
+
+impl Solutions {
+    /// Solve by Gaussian elimination, where column `j` of `A` has ones in rows `columns[j]` and
+    /// `target[i]` is row `i` of `b`.
+    pub fn new(columns: &[Vec<usize>], target: &[bool]) -> Result<Self, Inconsistent> {
+        let vars = columns.len();
+        // augmented matrix, with b in column `vars`, followed by which of the original rows
+        // each row is the sum of
+        let mut rows: Vec<Row> = target.iter().enumerate().map(|(i, &b)| {
+            let mut row = vec![0; (vars + 1 + target.len()).div_ceil(64)];
+            if b { flip(&mut row, vars); }
+            flip(&mut row, vars + 1 + i);
+            row
+        }).collect();
+        for (j, column) in columns.iter().enumerate() {
+            for &i in column { flip(&mut rows[i], j); }
+        }
+
+        // reduced row echelon form
+        let mut pivots: Vec<usize> = Vec::new();
+        for j in 0..vars {
+            let rank = pivots.len();
+            let Some(p) = (rank..rows.len()).find(|&i| get(&rows[i], j)) else { continue };
+            rows.swap(rank, p);
+            let (above, rest) = rows.split_at_mut(rank);
+            let (pivot, below) = rest.split_first_mut().unwrap();
+            for row in above.iter_mut().chain(below) {
+                if get(row, j) { xor_into(row, pivot); }
+            }
+            pivots.push(j);
+        }
+        // the remaining rows are all zero in A, so must be in b too
+        if let Some(row) = rows[pivots.len()..].iter().find(|row| get(row, vars)) {
+            return Err(Inconsistent { rows: (0..target.len()).filter(|&i| get(row, vars + 1 + i)).collect() });
+        }
+
+        let mut particular = vec![0; vars.div_ceil(64)];
+        for (row, &j) in rows.iter().zip(&pivots) {
+            if get(row, vars) { flip(&mut particular, j); }
+        }
+        let mut is_pivot = vec![false; vars];
+        for &j in &pivots { is_pivot[j] = true; }
+        let free_vars: Vec<usize> = (0..vars).filter(|&f| !is_pivot[f]).collect();
+        let null_space = free_vars.iter().map(|&f| {
+            let mut basis = vec![0; vars.div_ceil(64)];
+            flip(&mut basis, f);
+            for (row, &j) in rows.iter().zip(&pivots) {
+                if get(row, f) { flip(&mut basis, j); }
+            }
+            basis
+        }).collect();
+
+        let rank = pivots.len();
+        let mut reduced = vec![vec![0; rank.div_ceil(64)]; vars];
+        let mut reduced_target = vec![0; rank.div_ceil(64)];
+        for (k, row) in rows[..rank].iter().enumerate() {
+            for (j, column) in reduced.iter_mut().enumerate() {
+                if get(row, j) { flip(column, k); }
+            }
+            if get(row, vars) { flip(&mut reduced_target, k); }
+        }
+
+        Ok(Self { vars, particular, null_space, pivots, free_vars, reduced, reduced_target })
+    }
+
+    /// Number of free variables, so there are `2^free` solutions
+    pub fn free(&self) -> usize {
+        self.null_space.len()
+    }
+
+    /// Number of pivot variables, which are determined by the free ones
+    pub fn rank(&self) -> usize {
+        self.pivots.len()
+    }
+
+    /// Solution with the fewest ones, enumerating whichever of the free and pivot variables there
+    /// are fewer of, if there are at most [`MAX_FREE`]. Otherwise searches solutions by increasing
+    /// weight, giving up past [`MAX_SETS`] sets of columns.
+    pub fn min_weight(&self) -> Option<Vec<bool>> {
+        if self.free() <= self.rank().min(MAX_FREE) {
+            Some(self.min_weight_free())
+        } else if self.rank() <= MAX_FREE {
+            Some(self.min_weight_pivots())
+        } else {
+            self.min_weight_meet_in_middle()
+        }
+    }
+
+    /// Try every combination of the null space in Gray code order, so each step flips a single
+    /// basis vector
+    fn min_weight_free(&self) -> Vec<bool> {
+        let mut current = self.particular.clone();
+        let (mut best, mut best_weight) = (current.clone(), weight(&current));
+        for step in 1..1_u64 << self.free() {
+            xor_into(&mut current, &self.null_space[step.trailing_zeros() as usize]);
+            let current_weight = weight(&current);
+            if current_weight < best_weight {
+                best.clone_from(&current);
+                best_weight = current_weight;
+            }
+        }
+        (0..self.vars).map(|j| get(&best, j)).collect()
+    }
+
+    /// Try every combination of the pivot variables the free ones can give, found by breadth-first
+    /// search for the fewest free variables giving each. Pivots must fit in a word.
+    fn min_weight_pivots(&self) -> Vec<bool> {
+        let word = |row: &Row| row.first().map_or(0, |&w| w as usize);
+        let target = word(&self.reduced_target);
+        // fewest free variables summing to each combination of pivots (at most one per pivot)
+        let mut dist = vec![u8::MAX; 1 << self.rank()];
+        dist[0] = 0;
+        let mut queue = VecDeque::from([0]);
+        while let Some(v) = queue.pop_front() {
+            for &f in &self.free_vars {
+                let next = v ^ word(&self.reduced[f]);
+                if dist[next] == u8::MAX {
+                    dist[next] = dist[v] + 1;
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        // pivots take whatever the free variables leave of the target
+        let mut v = (0..dist.len()).filter(|&v| dist[v] != u8::MAX)
+            .min_by_key(|&v| dist[v] as u32 + (v ^ target).count_ones())
+            .unwrap();
+        let mut solution = vec![false; self.vars];
+        for (k, &j) in self.pivots.iter().enumerate() { solution[j] = (v ^ target) >> k & 1 == 1; }
+        while dist[v] > 0 {
+            let f = *self.free_vars.iter().find(|&&f| dist[v ^ word(&self.reduced[f])] == dist[v] - 1).unwrap();
+            solution[f] = true;
+            v ^= word(&self.reduced[f]);
+        }
+        solution
+    }
+
+    /// Try solutions of each weight `w` in turn, as a set of `w.div_ceil(2)` columns of `R`
+    /// matched with a set of `w / 2` from a table of their sums. The first match is disjoint, as
+    /// otherwise there'd be a lighter solution.
+    fn min_weight_meet_in_middle(&self) -> Option<Vec<bool>> {
+        let sum = |set: &[usize]| {
+            let mut sum = self.reduced_target.clone();
+            for &j in set { xor_into(&mut sum, &self.reduced[j]); }
+            sum
+        };
+        let solution = |set1: &[usize], set2: &[usize]| {
+            let mut solution = vec![false; self.vars];
+            for &j in set1.iter().chain(set2) { solution[j] = true; }
+            Some(solution)
+        };
+
+        // sets of `half` columns, by their sum with the target
+        let mut table: HashMap<Row, Vec<usize>> = HashMap::from([(sum(&[]), vec![])]);
+        let mut tried: usize = 1;
+        // every pivot variable is a column of the identity, so the weight is at most the rank
+        for half in 0..=self.rank() {
+            // weight 2 half: two tabulated sets solve R x = c if their entries differ by c
+            for (s, set1) in &table {
+                let mut other = s.clone();
+                xor_into(&mut other, &self.reduced_target);
+                if let Some(set2) = table.get(&other) { return solution(set1, set2); }
+            }
+            // weight 2 half + 1, tabulating the larger sets for the next weights
+            tried += (1..=half + 1).try_fold(1, |sets: usize, k| sets.checked_mul(self.vars + 1 - k).map(|sets| sets / k))?;
+            if tried > MAX_SETS { return None }
+            let mut next = HashMap::new();
+            for set1 in (0..self.vars).combinations(half + 1) {
+                let s = sum(&set1);
+                let mut other = s.clone();
+                xor_into(&mut other, &self.reduced_target);
+                if let Some(set2) = table.get(&other) { return solution(&set1, set2); }
+                next.entry(s).or_insert(set1);
+            }
+            table = next;
+        }
+        unreachable!("The particular solution has weight at most the rank")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{column_sums, Rng};
+
+    /// Fewest columns summing to `target`, by trying every subset
+    fn brute_force(columns: &[Vec<usize>], target: &[bool]) -> Option<usize> {
+        (0..1_u32 << columns.len()).filter(|subset| {
+            let presses: Vec<u64> = (0..columns.len()).map(|j| (subset >> j & 1).into()).collect();
+            column_sums(columns, &presses, target.len()).iter().map(|sum| sum % 2 == 1).eq(target.iter().copied())
+        }).map(|subset| subset.count_ones() as usize).min()
+    }
+
+    #[test]
+    fn min_weight_matches_brute_force() {
+        let mut rng = Rng::new(0x853c49e6748fea9b);
+        for _ in 0..300 {
+            let (rows, vars) = (1 + rng.below(8) as usize, 1 + rng.below(10) as usize);
+            let columns: Vec<Vec<usize>> = (0..vars).map(|_| (0..rows).filter(|_| rng.below(3) == 0).collect()).collect();
+            let target: Vec<bool> = (0..rows).map(|_| rng.below(2) == 0).collect();
+
+            let solution = Solutions::new(&columns, &target).map(|solutions| solutions.min_weight().unwrap());
+            if let Err(Inconsistent { rows }) = &solution {
+                assert!(columns.iter().all(|column| column.iter().filter(|i| rows.contains(i)).count() % 2 == 0));
+                assert!(rows.iter().filter(|&&i| target[i]).count() % 2 == 1);
+            }
+            let solution = solution.ok();
+            if let Some(presses) = &solution {
+                let presses: Vec<u64> = presses.iter().map(|&pressed| pressed.into()).collect();
+                assert!(column_sums(&columns, &presses, rows).iter().map(|sum| sum % 2 == 1).eq(target.iter().copied()));
+            }
+            assert_eq!(
+                solution.map(|presses| presses.iter().filter(|&&pressed| pressed).count()),
+                brute_force(&columns, &target), "{columns:?} {target:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn wide_rows() {
+        // more than a word of variables, each with its own light and one shared with its neighbour,
+        // and a last variable that's the sum of all the others
+        let vars = 70;
+        let mut columns: Vec<Vec<usize>> = (0..vars).map(|j| vec![j, vars + j / 2]).collect();
+        columns.push((0..vars).collect());
+        let target: Vec<bool> = (0..vars + vars / 2).map(|i| i < vars).collect();
+        let solutions = Solutions::new(&columns, &target).unwrap();
+        assert_eq!(solutions.free(), 1);
+        assert_eq!(solutions.min_weight().unwrap().iter().filter(|&&pressed| pressed).count(), 1);
+        let Err(Inconsistent { rows }) = Solutions::new(&columns, &vec![true; vars + vars / 2]) else { panic!("expected no solution") };
+        assert!(columns.iter().all(|column| column.iter().filter(|i| rows.contains(i)).count() % 2 == 0));
+        assert!(rows.len() % 2 == 1);
+    }
+
+    #[test]
+    fn strategies_agree() {
+        let mut rng = Rng::new(0x94d049bb133111eb);
+        for _ in 0..200 {
+            let (rows, vars) = (1 + rng.below(12) as usize, 1 + rng.below(18) as usize);
+            let columns: Vec<Vec<usize>> = (0..vars).map(|_| (0..rows).filter(|_| rng.below(3) == 0).collect()).collect();
+            let target: Vec<bool> = (0..rows).map(|_| rng.below(2) == 0).collect();
+            let Ok(solutions) = Solutions::new(&columns, &target) else { continue };
+
+            let expected = solutions.min_weight_free().iter().filter(|&&x| x).count();
+            for solution in [solutions.min_weight_pivots(), solutions.min_weight_meet_in_middle().unwrap()] {
+                let presses: Vec<u64> = solution.iter().map(|&pressed| pressed.into()).collect();
+                assert!(column_sums(&columns, &presses, rows).iter().map(|sum| sum % 2 == 1).eq(target.iter().copied()));
+                assert_eq!(solution.iter().filter(|&&x| x).count(), expected, "{columns:?} {target:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn too_many_free_variables() {
+        // every button toggles the one light, so the single pivot is enumerated instead
+        let columns = vec![vec![0]; MAX_FREE + 2];
+        let solutions = Solutions::new(&columns, &[true]).unwrap();
+        assert_eq!((solutions.free(), solutions.rank()), (MAX_FREE + 1, 1));
+        assert_eq!(solutions.min_weight().unwrap().iter().filter(|&&x| x).count(), 1);
+    }
+
+    #[test]
+    fn too_many_free_and_pivot_variables() {
+        // each light has its own button and one shared with the next light, so the lightest
+        // solution presses a shared button for each pair of lights on
+        let (lights, on) = (2 * MAX_FREE, 8);
+        let mut columns: Vec<Vec<usize>> = (0..lights).map(|i| vec![i]).collect();
+        columns.extend((0..lights).map(|i| vec![i, (i + 1) % lights]));
+        let target: Vec<bool> = (0..lights).map(|i| i < on).collect();
+        let solutions = Solutions::new(&columns, &target).unwrap();
+        assert!(solutions.free() > MAX_FREE && solutions.rank() > MAX_FREE);
+        assert_eq!(solutions.min_weight().unwrap().iter().filter(|&&x| x).count(), on / 2);
+
+        // every light on needs too many presses to find by meeting in the middle
+        let solutions = Solutions::new(&columns, &vec![true; lights]).unwrap();
+        assert_eq!(solutions.min_weight(), None);
+    }
+}
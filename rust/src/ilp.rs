@@ -0,0 +1,288 @@
+use itertools::Either;
+
+/// Why `A x = b` has no non-negative integer solution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Infeasible {
+    /// Row `i` of `A` is zero, but not of `b`
+    Unreachable(usize),
+    /// Summing rows of `A` with these multipliers (by row index) gives zero, but not for `b`
+    Inconsistent(Vec<(usize, i128)>),
+    /// There are solutions, but none are non-negative integers
+    NoIntegerSolution,
+}
+
+/// Non-negative integer solution of `A x = b` with the smallest sum of `x`, where column `j` of `A`
+/// has ones in rows `columns[j]` (counting repeats) and `target[i]` is row `i` of `b`.
+///
+/// Fraction-free Gaussian elimination writes each pivot variable in terms of the free variables,
+/// which are then searched by branch and bound. Each variable is bounded by every row it adds to,
+/// and at each step the range of the next free variable is narrowed to values that can still keep
+/// every pivot variable within its bounds.
+pub fn min_sum(columns: &[Vec<usize>], target: &[u64]) -> Result<Vec<u64>, Infeasible> {
+    let vars = columns.len();
+    let mut used = vec![false; target.len()];
+    for &i in columns.iter().flatten() { used[i] = true; }
+    if let Some(i) = (0..target.len()).find(|&i| !used[i] && target[i] != 0) {
+        return Err(Infeasible::Unreachable(i));
+    }
+
+    // all coefficients are non-negative, so no variable can exceed a row it's in
+    let upper: Vec<i128> = columns.iter()
+        .map(|column| column.iter().map(|&i| target[i] as i128).min().unwrap_or(0))
+        .collect();
+
+    // each row is `[A | b | y]` over the integers, where `A` counts how often each column adds to
+    // the row and `y` tracks which integer combination of the original rows it has become, so a
+    // contradiction found by elimination comes with its certificate
+    let mut rows: Vec<Vec<i128>> = target.iter().enumerate().map(|(i, &b)| {
+        let mut row = vec![0; vars + 1 + target.len()];
+        row[vars] = b as i128;
+        row[vars + 1 + i] = 1;
+        row
+    }).collect();
+    for (j, column) in columns.iter().enumerate() {
+        for &i in column { rows[i][j] += 1; }
+    }
+
+    // fraction-free elimination: column `j` is cleared from each other row by cross-multiplying,
+    // `row = p * row - row[j] * pivot_row` with `p` the pivot, which keeps every entry an integer.
+    // Entries would grow with each step, so rows are divided by the gcd of their entries
+    // afterwards. Pivots aren't scaled to one, so they're made positive, and each pivot variable is
+    // only an integer when its row's remainder divides by the pivot, which `Search` checks.
+    let mut pivots: Vec<usize> = Vec::new();
+    for j in 0..vars {
+        let rank = pivots.len();
+        let Some(p) = (rank..rows.len()).find(|&i| rows[i][j] != 0) else { continue };
+        rows.swap(rank, p);
+        if rows[rank][j] < 0 {
+            rows[rank].iter_mut().for_each(|v| *v = -*v);
+        }
+        let pivot = rows[rank].clone();
+        for (i, row) in rows.iter_mut().enumerate() {
+            if i == rank || row[j] == 0 { continue }
+            let scale = row[j];
+            for (v, &p) in row.iter_mut().zip(&pivot) {
+                *v = pivot[j] * *v - scale * p;
+            }
+            reduce(row);
+        }
+        pivots.push(j);
+    }
+    // rows past the rank have every coefficient cleared, so `y A = 0`, and a non-zero `y b` means
+    // no real solution exists, let alone an integer one
+    if let Some(row) = rows[pivots.len()..].iter().find(|row| row[vars] != 0) {
+        let multipliers = row[vars + 1..].iter().enumerate().filter(|&(_, &y)| y != 0).map(|(i, &y)| (i, y)).collect();
+        return Err(Infeasible::Inconsistent(multipliers));
+    }
+
+    let mut is_pivot = vec![false; vars];
+    for &j in &pivots { is_pivot[j] = true; }
+    let free: Vec<usize> = (0..vars).filter(|&j| !is_pivot[j]).collect();
+    let search = Search::new(&rows[..pivots.len()], &pivots, &free, &upper);
+
+    let mut best = None;
+    search.branch(0, &mut vec![0; pivots.len()], &mut vec![0; free.len()], 0, &mut best);
+    let (_, free_values) = best.ok_or(Infeasible::NoIntegerSolution)?;
+
+    let mut x = vec![0; vars];
+    for (&j, &v) in free.iter().zip(&free_values) { x[j] = v as u64; }
+    for (r, &j) in pivots.iter().enumerate() {
+        x[j] = search.pivot_value(r, search.row_sum(r, &free_values)).expect("Non-integer pivot in solution") as u64;
+    }
+    Ok(x)
+}
+
+/// Divide a row by the gcd of its entries
+fn reduce(row: &mut [i128]) {
+    let gcd = row.iter().fold(0, |a, &b| gcd(a, b.unsigned_abs()));
+    if gcd > 1 {
+        row.iter_mut().for_each(|v| *v /= gcd as i128);
+    }
+}
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 { (a, b) = (b, a % b); }
+    a
+}
+
+/// Branch and bound over the free variables of a system in reduced row echelon form, where pivot
+/// row `r` reads `pivot[r] * x_p + sum(coeffs[r][k] * x_free[k]) = rhs[r]`
+struct Search {
+    pivot: Vec<i128>,
+    coeffs: Vec<Vec<i128>>,
+    rhs: Vec<i128>,
+    /// Range of `sum(coeffs[r][k] * x_free[k])` keeping the pivot variable of row `r` in bounds
+    sum_range: Vec<(i128, i128)>,
+    free_upper: Vec<i128>,
+    /// Least and greatest contributions to each row's sum from the free variables after the `k`th
+    rest: Vec<Vec<(i128, i128)>>,
+    /// The objective scaled by the lcm of the pivots, as a constant plus a coefficient for each free variable
+    objective: (i128, Vec<i128>),
+    /// Least contribution to the scaled objective from the free variables after the `k`th
+    rest_objective: Vec<i128>,
+}
+
+impl Search {
+    fn new(rows: &[Vec<i128>], pivots: &[usize], free: &[usize], upper: &[i128]) -> Self {
+        let vars = upper.len();
+        let pivot: Vec<i128> = rows.iter().zip(pivots).map(|(row, &j)| row[j]).collect();
+        let coeffs: Vec<Vec<i128>> = rows.iter().map(|row| free.iter().map(|&j| row[j]).collect()).collect();
+        let rhs: Vec<i128> = rows.iter().map(|row| row[vars]).collect();
+        let sum_range = (0..rows.len()).map(|r| (rhs[r] - pivot[r] * upper[pivots[r]], rhs[r])).collect();
+        let free_upper: Vec<i128> = free.iter().map(|&j| upper[j]).collect();
+
+        let mut rest = vec![vec![(0, 0); rows.len()]; free.len() + 1];
+        for k in (0..free.len()).rev() {
+            for r in 0..rows.len() {
+                let ((lo, hi), extreme) = (rest[k + 1][r], coeffs[r][k] * free_upper[k]);
+                rest[k][r] = (lo + extreme.min(0), hi + extreme.max(0));
+            }
+        }
+        let rest = rest[1..].to_vec();
+
+        // each pivot variable is (rhs - sum) / pivot, so scale everything by the lcm of the pivots
+        let scale = pivot.iter().fold(1, |l, &p| l / gcd(l as u128, p as u128) as i128 * p);
+        let constant = (0..rows.len()).map(|r| scale / pivot[r] * rhs[r]).sum();
+        let linear: Vec<i128> = (0..free.len())
+            .map(|k| scale - (0..rows.len()).map(|r| scale / pivot[r] * coeffs[r][k]).sum::<i128>())
+            .collect();
+        let mut rest_objective = vec![0; free.len() + 1];
+        for k in (0..free.len()).rev() {
+            rest_objective[k] = rest_objective[k + 1] + (linear[k] * free_upper[k]).min(0);
+        }
+
+        Self {
+            pivot, coeffs, rhs, sum_range, free_upper, rest,
+            objective: (constant, linear), rest_objective: rest_objective[1..].to_vec(),
+        }
+    }
+
+    fn row_sum(&self, r: usize, free_values: &[i128]) -> i128 {
+        self.coeffs[r].iter().zip(free_values).map(|(a, x)| a * x).sum()
+    }
+
+    /// The pivot variable of row `r` given the sum of its free terms, if it's an integer
+    fn pivot_value(&self, r: usize, sum: i128) -> Option<i128> {
+        let numerator = self.rhs[r] - sum;
+        (numerator % self.pivot[r] == 0).then(|| numerator / self.pivot[r])
+    }
+
+    /// Try every value of free variable `k` that could improve on `best`, given the sums of
+    /// each row and the scaled objective so far
+    fn branch(&self, k: usize, sums: &mut [i128], values: &mut [i128], objective: i128, best: &mut Option<(i128, Vec<i128>)>) {
+        if k == values.len() {
+            let feasible = sums.iter().enumerate().all(|(r, &sum)| {
+                let (lo, hi) = self.sum_range[r];
+                (lo..=hi).contains(&sum) && self.pivot_value(r, sum).is_some()
+            });
+            let total = self.objective.0 + objective;
+            if feasible && best.as_ref().is_none_or(|(best, _)| total < *best) {
+                *best = Some((total, values.to_vec()));
+            }
+            return;
+        }
+
+        // narrow the range of x so every row can still reach its range
+        let (mut lo, mut hi) = (0, self.free_upper[k]);
+        for (r, &sum) in sums.iter().enumerate() {
+            let (rest_lo, rest_hi) = self.rest[k][r];
+            let (min, max) = (self.sum_range[r].0 - sum - rest_hi, self.sum_range[r].1 - sum - rest_lo);
+            match self.coeffs[r][k] {
+                0 if min > 0 || max < 0 => return,
+                0 => {},
+                a if a > 0 => (lo, hi) = (lo.max(div_ceil(min, a)), hi.min(div_floor(max, a))),
+                a => (lo, hi) = (lo.max(div_ceil(max, a)), hi.min(div_floor(min, a))),
+            }
+        }
+        if lo > hi { return }
+
+        // cheapest values first, stopping when even the best case can't improve
+        let c = self.objective.1[k];
+        let order = if c >= 0 { Either::Left(lo..=hi) } else { Either::Right((lo..=hi).rev()) };
+        for x in order {
+            let objective = objective + c * x;
+            if best.as_ref().is_some_and(|(best, _)| self.objective.0 + objective + self.rest_objective[k] >= *best) {
+                break;
+            }
+            for (r, sum) in sums.iter_mut().enumerate() { *sum += self.coeffs[r][k] * x; }
+            values[k] = x;
+            self.branch(k + 1, sums, values, objective, best);
+            for (r, sum) in sums.iter_mut().enumerate() { *sum -= self.coeffs[r][k] * x; }
+        }
+    }
+}
+
+fn div_floor(a: i128, b: i128) -> i128 {
+    let q = a / b;
+    if (a % b != 0) && ((a < 0) != (b < 0)) { q - 1 } else { q }
+}
+
+fn div_ceil(a: i128, b: i128) -> i128 {
+    -div_floor(-a, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{column_sums, Rng};
+
+    /// Smallest sum of a solution, by trying every combination within the bounds
+    fn brute_force(columns: &[Vec<usize>], target: &[u64]) -> Option<u64> {
+        fn search(columns: &[Vec<usize>], remaining: &mut [u64], presses: u64) -> Option<u64> {
+            let Some((column, rest)) = columns.split_first() else {
+                return remaining.iter().all(|&v| v == 0).then_some(presses);
+            };
+            let mut best = None;
+            let mut n = 0;
+            loop {
+                best = best.into_iter().chain(search(rest, remaining, presses + n)).min();
+                if column.is_empty() || column.iter().any(|&i| remaining[i] == 0) { break }
+                for &i in column { remaining[i] -= 1; }
+                n += 1;
+            }
+            for &i in column { remaining[i] += n; }
+            best
+        }
+        search(columns, &mut target.to_vec(), 0)
+    }
+
+    #[test]
+    fn min_sum_matches_brute_force() {
+        let mut rng = Rng::new(0xda942042e4dd58b5);
+        for _ in 0..300 {
+            let (rows, vars) = (1 + rng.below(5) as usize, 1 + rng.below(6) as usize);
+            let columns: Vec<Vec<usize>> = (0..vars).map(|_| (0..rows).filter(|_| rng.below(2) == 0).collect()).collect();
+            // mostly solvable, from random presses
+            let presses: Vec<u64> = (0..vars).map(|_| rng.below(5)).collect();
+            let mut target = column_sums(&columns, &presses, rows);
+            if rng.below(4) == 0 { target[0] += 1; }
+
+            let solution = min_sum(&columns, &target);
+            if let Err(Infeasible::Inconsistent(multipliers)) = &solution {
+                let combination = |row: &dyn Fn(usize) -> i128| multipliers.iter().map(|&(i, y)| y * row(i)).sum::<i128>();
+                assert!(columns.iter().all(|column| combination(&|i| column.iter().filter(|&&r| r == i).count() as i128) == 0));
+                assert_ne!(combination(&|i| target[i] as i128), 0);
+            }
+            let solution = solution.ok();
+            if let Some(presses) = &solution {
+                assert_eq!(column_sums(&columns, presses, rows), target);
+            }
+            assert_eq!(solution.map(|presses| presses.iter().sum()), brute_force(&columns, &target), "{columns:?} {target:?}");
+        }
+    }
+
+    #[test]
+    fn example_machines() {
+        let solve = |columns: &[&[usize]], target: &[u64]| {
+            let columns: Vec<Vec<usize>> = columns.iter().map(|column| column.to_vec()).collect();
+            min_sum(&columns, target).map(|presses| presses.iter().sum::<u64>())
+        };
+        assert_eq!(solve(&[&[3], &[1, 3], &[2], &[2, 3], &[0, 2], &[0, 1]], &[3, 5, 4, 7]), Ok(10));
+        assert_eq!(solve(&[&[0, 2, 3, 4], &[2, 3], &[0, 4], &[0, 1, 2], &[1, 2, 3, 4]], &[7, 5, 12, 7, 2]), Ok(12));
+        assert_eq!(solve(&[&[0, 1, 2, 3, 4], &[0, 3, 4], &[0, 1, 2, 4, 5], &[1, 2]], &[10, 11, 11, 5, 10, 5]), Ok(11));
+        assert_eq!(solve(&[&[0, 1]], &[1, 2]), Err(Infeasible::Inconsistent(vec![(0, -1), (1, 1)])));
+        assert_eq!(solve(&[&[0, 1], &[1]], &[2, 1]), Err(Infeasible::NoIntegerSolution));
+        assert_eq!(solve(&[&[0]], &[1, 2]), Err(Infeasible::Unreachable(1)));
+        assert_eq!(solve(&[&[], &[0]], &[3]), Ok(3));
+    }
+}
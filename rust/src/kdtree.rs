@@ -0,0 +1,316 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use petgraph::unionfind::UnionFind;
+
+use super::point::{Metric, Point};
+
+/// Edge between points `i < j` with their distance, as `(distance, i, j)` so edges order by
+/// distance with ties broken by index
+pub type Edge = (u64, u32, u32);
+
+/// Static k-d tree over a slice of points, stored implicitly as a permutation of the point indices.
+///
+/// Each subrange of `order` is a subtree, with its root at the middle, split along axis `depth % N`.
+pub struct KdTree<'a, const N: usize> {
+    points: &'a [Point<N>],
+    order: Vec<u32>,
+    metric: Metric,
+    /// Bounding box of the subtree rooted at each position of `order`
+    bounds: Vec<[Point<N>; 2]>,
+}
+
+impl<'a, const N: usize> KdTree<'a, N> {
+    pub fn new(points: &'a [Point<N>], metric: Metric) -> Self {
+        assert!(points.len() <= u32::MAX as usize, "Too many points for a KdTree");
+        let mut order: Vec<u32> = (0..points.len() as u32).collect();
+        Self::build(points, &mut order, 0);
+        let mut bounds = vec![[[0; N]; 2]; points.len()];
+        Self::build_bounds(points, &order, 0, &mut bounds);
+        Self { points, order, metric, bounds }
+    }
+
+    fn build(points: &[Point<N>], order: &mut [u32], depth: usize) {
+        if order.len() <= 1 { return; }
+        let axis = depth % N;
+        let mid = order.len() / 2;
+        order.select_nth_unstable_by_key(mid, |&i| points[i as usize][axis]);
+        let (left, right) = order.split_at_mut(mid);
+        Self::build(points, left, depth + 1);
+        Self::build(points, &mut right[1..], depth + 1);
+    }
+
+    /// Fill in `bounds` for the subtree at `order`, which starts at position `offset`
+    fn build_bounds(points: &[Point<N>], order: &[u32], offset: usize, bounds: &mut [[Point<N>; 2]]) -> Option<[Point<N>; 2]> {
+        if order.is_empty() { return None; }
+        let mid = order.len() / 2;
+        let mut bound = [points[order[mid] as usize]; 2];
+        let left = Self::build_bounds(points, &order[..mid], offset, bounds);
+        let right = Self::build_bounds(points, &order[mid + 1..], offset + mid + 1, bounds);
+        for [min, max] in left.into_iter().chain(right) {
+            for axis in 0..N {
+                bound[0][axis] = bound[0][axis].min(min[axis]);
+                bound[1][axis] = bound[1][axis].max(max[axis]);
+            }
+        }
+        bounds[offset + mid] = bound;
+        Some(bound)
+    }
+
+    pub fn points(&self) -> &'a [Point<N>] {
+        self.points
+    }
+
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    /// The `k` points closest to point `query`, ordered by `(distance, index)`, among those with
+    /// an index greater than `query` and a `(distance, index)` greater than `after`.
+    pub fn nearest_after(&self, query: u32, k: usize, after: Option<(u64, u32)>) -> Vec<(u64, u32)> {
+        let mut best = BinaryHeap::with_capacity(k + 1);
+        if k > 0 {
+            self.search(&self.order, 0, query, k, after, &mut best);
+        }
+        best.into_sorted_vec()
+    }
+
+    fn search(
+        &self, order: &[u32], depth: usize, query: u32, k: usize, after: Option<(u64, u32)>,
+        best: &mut BinaryHeap<(u64, u32)>,
+    ) {
+        if order.is_empty() { return; }
+        let mid = order.len() / 2;
+        let node = order[mid];
+        let q = &self.points[query as usize];
+        let p = &self.points[node as usize];
+
+        let key = (self.metric.distance(q, p), node);
+        if node > query && after.is_none_or(|after| key > after) && (best.len() < k || key < *best.peek().unwrap()) {
+            best.push(key);
+            if best.len() > k { best.pop(); }
+        }
+
+        let axis = depth % N;
+        let (near, far) = if q[axis] < p[axis] {
+            (&order[..mid], &order[mid + 1..])
+        } else {
+            (&order[mid + 1..], &order[..mid])
+        };
+        self.search(near, depth + 1, query, k, after, best);
+        // ties on the splitting plane may still beat the worst candidate by index
+        if best.len() < k || self.metric.axis_distance(q[axis].abs_diff(p[axis])) <= best.peek().unwrap().0 {
+            self.search(far, depth + 1, query, k, after, best);
+        }
+    }
+
+    /// All pairs of points, lazily in increasing order of distance (ties broken by index)
+    pub fn closest_pairs(&self) -> ClosestPairs<'_, 'a, N> {
+        ClosestPairs::new(self)
+    }
+
+    /// Edges of the minimum spanning tree in increasing order, the same edges Kruskal's algorithm
+    /// takes from [`closest_pairs`](Self::closest_pairs).
+    ///
+    /// Borůvka's algorithm: each round, every component is joined to its nearest other component,
+    /// found by a nearest neighbour query from each of its points. Queries skip subtrees lying
+    /// entirely within the query's component or further away than the component's best edge so
+    /// far, and the number of components at least halves each round.
+    pub fn minimum_spanning_tree(&self) -> Vec<Edge> {
+        let n = self.points.len();
+        let mut union_find: UnionFind<u32> = UnionFind::new(n);
+        let mut edges: Vec<Edge> = Vec::with_capacity(n.saturating_sub(1));
+        let mut component = vec![0; n];
+        let mut subtree_component = vec![None; n];
+
+        while edges.len() + 1 < n {
+            for (i, c) in component.iter_mut().enumerate() { *c = union_find.find_mut(i as u32); }
+            self.label_subtrees(0, n, &component, &mut subtree_component);
+
+            // shortest edge leaving each component, by its root
+            let mut shortest: Vec<Option<Edge>> = vec![None; n];
+            for query in 0..n as u32 {
+                let best = &mut shortest[component[query as usize] as usize];
+                self.nearest_other(0, n, 0, query, &component, &subtree_component, best);
+            }
+            for (dist, i, j) in shortest.into_iter().flatten() {
+                // both components may pick the same edge
+                if union_find.union(i, j) { edges.push((dist, i, j)); }
+            }
+        }
+        edges.sort_unstable();
+        edges
+    }
+
+    /// Label the subtree at positions `start..end` of `order` with the component of all its
+    /// points, if they're all in one, returning that label
+    fn label_subtrees(&self, start: usize, end: usize, component: &[u32], labels: &mut [Option<u32>]) -> Option<u32> {
+        let mid = start + (end - start) / 2;
+        let c = component[self.order[mid] as usize];
+        let left = if start < mid { self.label_subtrees(start, mid, component, labels) } else { Some(c) };
+        let right = if mid + 1 < end { self.label_subtrees(mid + 1, end, component, labels) } else { Some(c) };
+        labels[mid] = (left == Some(c) && right == Some(c)).then_some(c);
+        labels[mid]
+    }
+
+    /// Improve `best` with the closest point to `query` in another component, within the subtree
+    /// at positions `start..end` of `order`
+    #[allow(clippy::too_many_arguments)]
+    fn nearest_other(
+        &self, start: usize, end: usize, depth: usize, query: u32, component: &[u32], subtree_component: &[Option<u32>],
+        best: &mut Option<Edge>,
+    ) {
+        if start == end { return; }
+        let mid = start + (end - start) / 2;
+        let own = component[query as usize];
+        let q = &self.points[query as usize];
+        if subtree_component[mid] == Some(own) { return; }
+        // ties with the best distance may still win by index
+        if best.is_some_and(|(dist, _, _)| self.box_distance(q, mid) > dist) { return; }
+
+        let node = self.order[mid];
+        let p = &self.points[node as usize];
+        if component[node as usize] != own {
+            let edge = (self.metric.distance(q, p), query.min(node), query.max(node));
+            if best.is_none_or(|best| edge < best) { *best = Some(edge); }
+        }
+
+        let axis = depth % N;
+        let (near, far) = if q[axis] < p[axis] { ((start, mid), (mid + 1, end)) } else { ((mid + 1, end), (start, mid)) };
+        self.nearest_other(near.0, near.1, depth + 1, query, component, subtree_component, best);
+        self.nearest_other(far.0, far.1, depth + 1, query, component, subtree_component, best);
+    }
+
+    /// Distance from `q` to the nearest point of the bounding box of the subtree rooted at `pos`
+    fn box_distance(&self, q: &Point<N>, pos: usize) -> u64 {
+        let [min, max] = &self.bounds[pos];
+        let nearest: Point<N> = std::array::from_fn(|axis| q[axis].clamp(min[axis], max[axis]));
+        self.metric.distance(q, &nearest)
+    }
+}
+
+/// Iterator over the pairs of points in a [`KdTree`] in increasing order of distance.
+///
+/// Each point keeps a buffer of its nearest unvisited neighbours (with a greater index), which is
+/// refilled with a larger batch when exhausted. A heap holds the closest pair from each buffer.
+pub struct ClosestPairs<'t, 'a, const N: usize> {
+    tree: &'t KdTree<'a, N>,
+    heap: BinaryHeap<Reverse<(u64, u32, u32)>>,
+    /// Remaining neighbours of each point, furthest first
+    buffers: Vec<Vec<(u64, u32)>>,
+    batch_sizes: Vec<u32>,
+}
+
+const INITIAL_BATCH: u32 = 4;
+const MAX_BATCH: u32 = 1024;
+
+impl<'t, 'a, const N: usize> ClosestPairs<'t, 'a, N> {
+    fn new(tree: &'t KdTree<'a, N>) -> Self {
+        let n = tree.points.len();
+        let mut pairs = Self {
+            tree,
+            heap: BinaryHeap::with_capacity(n),
+            buffers: vec![Vec::new(); n],
+            batch_sizes: vec![INITIAL_BATCH; n],
+        };
+        for i in 0..n as u32 {
+            pairs.advance(i, None);
+        }
+        pairs
+    }
+
+    /// Push the next neighbour of point `i` after `after` onto the heap, refilling its buffer if needed
+    fn advance(&mut self, i: u32, after: Option<(u64, u32)>) {
+        let buffer = &mut self.buffers[i as usize];
+        if buffer.is_empty() {
+            let batch = &mut self.batch_sizes[i as usize];
+            *buffer = self.tree.nearest_after(i, *batch as usize, after);
+            buffer.reverse();
+            *batch = (*batch * 2).min(MAX_BATCH);
+        }
+        if let Some((dist, j)) = buffer.pop() {
+            self.heap.push(Reverse((dist, i, j)));
+        }
+    }
+}
+
+impl<const N: usize> Iterator for ClosestPairs<'_, '_, N> {
+    type Item = (u32, u32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((dist, i, j)) = self.heap.pop()?;
+        self.advance(i, Some((dist, j)));
+        Some((i, j))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{pairs, Rng};
+
+    /// Deterministic pseudo-random points around the origin, with plenty of duplicate coordinates and distances
+    fn points(n: usize, range: u64) -> Vec<Point<3>> {
+        Rng::new(0x2545f4914f6cdd1d).points(n, range)
+    }
+
+    fn brute_force(points: &[Point<3>], metric: Metric) -> Vec<(u32, u32)> {
+        let mut pairs: Vec<(u32, u32)> = pairs(points.len()).map(|(i, j)| (i as u32, j as u32)).collect();
+        pairs.sort_by_key(|&(i, j)| (metric.distance(&points[i as usize], &points[j as usize]), i, j));
+        pairs
+    }
+
+    #[test]
+    fn closest_pairs_matches_brute_force() {
+        for metric in [Metric::SqEuclidean, Metric::Manhattan, Metric::Chebyshev] {
+            for (n, range) in [(0, 10), (1, 10), (2, 10), (50, 5), (300, 1000)] {
+                let points = points(n, range);
+                let tree = KdTree::new(&points, metric);
+                assert_eq!(
+                    tree.closest_pairs().collect::<Vec<_>>(), brute_force(&points, metric),
+                    "{n} points in 0..{range}, {metric:?}"
+                );
+            }
+        }
+    }
+
+    /// Minimum spanning tree by Kruskal's algorithm over every pair
+    fn kruskal(points: &[Point<3>], metric: Metric) -> Vec<Edge> {
+        let mut union_find: UnionFind<u32> = UnionFind::new(points.len());
+        brute_force(points, metric).into_iter()
+            .filter(|&(i, j)| union_find.union(i, j))
+            .map(|(i, j)| (metric.distance(&points[i as usize], &points[j as usize]), i, j))
+            .collect()
+    }
+
+    #[test]
+    fn minimum_spanning_tree_matches_kruskal() {
+        for metric in [Metric::SqEuclidean, Metric::Manhattan, Metric::Chebyshev] {
+            for (n, range) in [(0, 10), (1, 10), (2, 10), (50, 5), (300, 1000)] {
+                let points = points(n, range);
+                let tree = KdTree::new(&points, metric);
+                assert_eq!(tree.minimum_spanning_tree(), kruskal(&points, metric), "{n} points in 0..{range}, {metric:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn minimum_spanning_tree_of_clusters() {
+        // two far apart clusters, joined by a single long edge
+        let mut rng = Rng::new(0x6a09e667f3bcc909);
+        let points: Vec<Point<3>> = (0..2).flat_map(|c| rng.points::<3>(2000, 1000).into_iter().map(move |[x, y, z]| [x + c * 1_000_000, y, z])).collect();
+        let edges = KdTree::new(&points, Metric::SqEuclidean).minimum_spanning_tree();
+        assert_eq!(edges.len(), points.len() - 1);
+        assert!(edges[..edges.len() - 1].iter().all(|&(dist, _, _)| dist < 1_000_000));
+        let &(_, i, j) = edges.last().unwrap();
+        assert!((i < 2000) != (j < 2000));
+    }
+
+    #[test]
+    fn nearest_after_skips_earlier_neighbours() {
+        let points = [[0, 0, 0], [5, 0, 0], [1, 0, 0], [2, 0, 0], [1, 0, 0]];
+        let tree = KdTree::new(&points, Metric::SqEuclidean);
+        assert_eq!(tree.nearest_after(0, 2, None), vec![(1, 2), (1, 4)]);
+        assert_eq!(tree.nearest_after(0, 2, Some((1, 2))), vec![(1, 4), (4, 3)]);
+        assert_eq!(tree.nearest_after(3, 5, None), vec![(1, 4)]);
+    }
+}
@@ -1,7 +1,19 @@
-#![feature(int_roundings)]
 #![feature(ascii_char)]
+#![feature(substr_range)]
 
-use std::{sync::OnceLock, path::{Path, PathBuf}, sync::atomic::{AtomicU8, Ordering}};
+use std::{env, fmt, fs::File, io::{self, BufRead, BufReader}, sync::OnceLock, path::{Path, PathBuf}, sync::atomic::{AtomicU8, Ordering}};
+
+pub mod answers;
+pub mod error;
+pub mod gf2;
+pub mod ilp;
+pub mod kdtree;
+pub mod point;
+pub mod polygon;
+pub mod render;
+pub mod solution;
+#[cfg(test)]
+mod testing;
 
 pub mod day1;
 pub mod day2;
@@ -16,9 +28,31 @@ pub mod day10;
 pub mod day11;
 pub mod day12;
 
+pub use error::{Error, Result};
+pub use solution::{Answer, Answers, Day, Report, Solution};
+
+/// All registered days, in order
+pub static DAYS: &[Day] = &[
+    Day::new::<day1::Day1>(),
+    Day::new::<day2::Day2>(),
+    Day::new::<day3::Day3>(),
+    Day::new::<day4::Day4>(),
+    Day::new::<day5::Day5>(),
+    Day::new::<day6::Day6>(),
+    Day::new::<day7::Day7>(),
+    Day::new::<day8::Day8>(),
+    Day::new::<day9::Day9>(),
+    Day::new::<day10::Day10>(),
+    Day::new::<day11::Day11>(),
+    Day::new::<day12::Day12>(),
+];
+
 static VERBOSITY: AtomicU8 = AtomicU8::new(0);
 static INPUT_DIR: OnceLock<PathBuf> = OnceLock::new();
 
+/// Environment variable overriding the input directory
+pub const INPUT_DIR_VAR: &str = "ADVENT_INPUT_DIR";
+
 pub fn set_verbosity(value: u8) {
     VERBOSITY.store(value, Ordering::Release);
 }
@@ -27,9 +61,55 @@ pub fn verbosity() -> u8 {
     VERBOSITY.load(Ordering::Relaxed)
 }
 
+/// Directory containing `dayN.txt` inputs. Defaults to `input/` in the source checkout.
 pub fn input_dir() -> &'static Path {
     INPUT_DIR.get_or_init(|| {
-        let buf = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-        buf.join("../input").canonicalize().expect("Failed to get absolute path")
+        let buf = match env::var_os(INPUT_DIR_VAR) {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../input"),
+        };
+        buf.canonicalize().unwrap_or(buf)
     })
-}
\ No newline at end of file
+}
+
+pub fn input_path(day: u8, test: bool) -> PathBuf {
+    input_dir().join(if test { format!("day{day}_test.txt") } else { format!("day{day}.txt") })
+}
+
+/// Where to read a day's input from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputSource {
+    Stdin,
+    File(PathBuf),
+}
+
+impl InputSource {
+    /// Parse a command line path, where `-` means stdin
+    pub fn from_arg(path: &Path) -> Self {
+        if path == Path::new("-") { InputSource::Stdin } else { InputSource::File(path.to_owned()) }
+    }
+
+    /// File name without extension, used to look up known answers
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            InputSource::Stdin => None,
+            InputSource::File(path) => path.file_stem().and_then(|s| s.to_str()),
+        }
+    }
+
+    pub fn open(&self) -> Result<Box<dyn BufRead>> {
+        Ok(match self {
+            InputSource::Stdin => Box::new(io::stdin().lock()),
+            InputSource::File(path) => Box::new(BufReader::new(File::open(path)?)),
+        })
+    }
+}
+
+impl fmt::Display for InputSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputSource::Stdin => write!(f, "<stdin>"),
+            InputSource::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::io::BufRead;
+
+use super::{Error, Result};
+
+/// Point with `N` signed integer coordinates
+pub type Point<const N: usize> = [i64; N];
+
+/// Parse a point from `N` comma-separated coordinates
+pub fn parse_point<const N: usize>(s: &str) -> Option<Point<N>> {
+    s.split(',').map(|w| w.trim().parse().ok())
+        .collect::<Option<Vec<i64>>>()
+        .and_then(|coords| coords.try_into().ok())
+}
+
+/// Parse a point from line `n` of the input (counting from 1)
+pub fn parse_point_line<const N: usize>(line: &str, n: usize) -> Result<Point<N>> {
+    parse_point(line).ok_or_else(|| {
+        Error::parse(format!("Invalid coordinate, expected {N} comma-separated integers"), line).at_line(n)
+    })
+}
+
+/// Parse one point per line
+pub fn parse_points<const N: usize, R: BufRead>(file: R) -> Result<Vec<Point<N>>> {
+    file.lines().enumerate().map(|(i, line)| parse_point_line(&line?, i + 1)).collect()
+}
+
+/// Smallest box containing all the points, as its minimum and maximum corners
+pub fn bounding_box<const N: usize>(points: &[Point<N>]) -> Option<[Point<N>; 2]> {
+    let (first, rest) = points.split_first()?;
+    Some(rest.iter().fold([*first, *first], |[mut min, mut max], point| {
+        for axis in 0..N {
+            min[axis] = min[axis].min(point[axis]);
+            max[axis] = max[axis].max(point[axis]);
+        }
+        [min, max]
+    }))
+}
+
+/// Distance between points. All metrics are integer valued, so `SqEuclidean` is squared.
+///
+/// Distances saturate at `u64::MAX` rather than overflowing, which only happens for coordinates
+/// beyond ±2^31 with `SqEuclidean` or ±2^62 otherwise.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Metric {
+    #[default]
+    #[value(name = "euclidean")]
+    SqEuclidean,
+    Manhattan,
+    Chebyshev,
+}
+
+impl Metric {
+    pub fn distance<const N: usize>(self, p1: &Point<N>, p2: &Point<N>) -> u64 {
+        let diffs = p1.iter().zip(p2).map(|(&l, &r)| l.abs_diff(r));
+        match self {
+            Metric::SqEuclidean => diffs.map(|d| d.saturating_pow(2)).fold(0, u64::saturating_add),
+            Metric::Manhattan => diffs.fold(0, u64::saturating_add),
+            Metric::Chebyshev => diffs.max().unwrap_or(0),
+        }
+    }
+
+    /// Distance between points differing by `diff` along a single axis, a lower bound for any
+    /// points that far apart along that axis
+    pub fn axis_distance(self, diff: u64) -> u64 {
+        match self {
+            Metric::SqEuclidean => diff.saturating_pow(2),
+            Metric::Manhattan | Metric::Chebyshev => diff,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(parse_point("162,817,812"), Some([162, 817, 812]));
+        assert_eq!(parse_point("7, 1"), Some([7, 1]));
+        assert_eq!(parse_point("-7,0"), Some([-7, 0]));
+        assert_eq!(parse_point::<2>("7,1,3"), None);
+        assert_eq!(parse_point::<3>("7,1"), None);
+        assert_eq!(parse_point::<2>("7,x"), None);
+    }
+
+    #[test]
+    fn bounds() {
+        assert_eq!(bounding_box::<2>(&[]), None);
+        assert_eq!(bounding_box(&[[3, -1]]), Some([[3, -1], [3, -1]]));
+        assert_eq!(bounding_box(&[[3, -1, 0], [-2, 4, 0], [1, 1, 7]]), Some([[-2, -1, 0], [3, 4, 7]]));
+    }
+
+    #[test]
+    fn metrics() {
+        let (p1, p2) = ([1, 5, 2], [4, 1, 2]);
+        assert_eq!(Metric::SqEuclidean.distance(&p1, &p2), 25);
+        assert_eq!(Metric::Manhattan.distance(&p1, &p2), 7);
+        assert_eq!(Metric::Chebyshev.distance(&p1, &p2), 4);
+
+        let (p1, p2) = ([-3, 2], [4, -2]);
+        assert_eq!(Metric::SqEuclidean.distance(&p1, &p2), 65);
+        assert_eq!(Metric::Manhattan.distance(&p1, &p2), 11);
+
+        let (p1, p2) = ([i64::MIN, 0], [i64::MAX, 0]);
+        assert_eq!(Metric::SqEuclidean.distance(&p1, &p2), u64::MAX);
+        assert_eq!(Metric::Chebyshev.distance(&p1, &p2), u64::MAX);
+    }
+}
@@ -0,0 +1,206 @@
+use std::{collections::{BTreeMap, HashMap}, fmt};
+
+use super::{point::Point, Error, Result};
+
+/// Direction a loop is traversed in, as displayed with y increasing downwards
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Winding {
+    Clockwise,
+    CounterClockwise,
+}
+
+impl fmt::Display for Winding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Winding::Clockwise => write!(f, "clockwise"),
+            Winding::CounterClockwise => write!(f, "counter-clockwise"),
+        }
+    }
+}
+
+/// Which points a set of loops encloses, from the number of times the loops wind around them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum FillRule {
+    /// Inside an odd number of loops, so nested loops alternate between holes and islands
+    #[default]
+    EvenOdd,
+    /// Wound around at least once, so only loops running the opposite way to their
+    /// surroundings make holes
+    NonZero,
+}
+
+impl FillRule {
+    pub fn inside(self, winding: i32) -> bool {
+        match self {
+            FillRule::EvenOdd => winding % 2 != 0,
+            FillRule::NonZero => winding != 0,
+        }
+    }
+}
+
+/// Twice the signed area of a closed loop (shoelace formula). Positive for clockwise loops.
+pub fn signed_area2(poly: &[Point<2>]) -> i128 {
+    let n = poly.len();
+    (0..n).map(|i| {
+        let ([x1, y1], [x2, y2]) = (poly[i], poly[(i + 1) % n]);
+        x1 as i128 * y2 as i128 - x2 as i128 * y1 as i128
+    }).sum()
+}
+
+/// Length of a closed loop of axis-aligned edges, which is also the number of tiles on it
+pub fn boundary_length(poly: &[Point<2>]) -> u64 {
+    let n = poly.len();
+    (0..n).map(|i| {
+        let ([x1, y1], [x2, y2]) = (poly[i], poly[(i + 1) % n]);
+        x1.abs_diff(x2).saturating_add(y1.abs_diff(y2))
+    }).fold(0, u64::saturating_add)
+}
+
+/// Number of tiles on or inside a closed loop of axis-aligned edges through tile centres.
+///
+/// By Pick's theorem, a loop with area `A` and `B` tiles on its boundary has `A - B/2 + 1`
+/// tiles strictly inside.
+pub fn tile_count(poly: &[Point<2>]) -> u128 {
+    (signed_area2(poly).unsigned_abs() + boundary_length(poly) as u128) / 2 + 1
+}
+
+/// Edge from vertex `start` to the next one, along a line at `at` spanning `from..=to`
+#[derive(Debug, Clone, Copy)]
+struct Edge {
+    start: usize,
+    at: i64,
+    from: i64,
+    to: i64,
+}
+
+/// Check that consecutive tiles form a closed loop of axis-aligned edges, which doesn't repeat
+/// a tile or touch itself anywhere other than between consecutive edges. Errors are reported at
+/// the input line of the offending tile, with the first tile on line `first_line`, and give the
+/// index of each tile they mention in the loop.
+pub fn validate(poly: &[Point<2>], first_line: usize) -> Result<Winding> {
+    let n = poly.len();
+    if n < 4 {
+        return Err(Error::parse(format!("A loop needs at least 4 tiles, found {n}"), "").at_line(first_line));
+    }
+    let tile_error = |message: String, i: usize| {
+        let [x, y] = poly[i];
+        Error::parse(format!("Vertex {i}: {message}"), format!("{x},{y}")).at_line(first_line + i)
+    };
+    let vertex = |i: usize| format!("vertex {i} on line {}", first_line + i);
+
+    let mut seen: HashMap<Point<2>, usize> = HashMap::with_capacity(n);
+    for (i, tile) in poly.iter().enumerate() {
+        if let Some(first) = seen.insert(*tile, i) {
+            return Err(tile_error(format!("Duplicate of {}", vertex(first)), i));
+        }
+    }
+
+    let (mut horizontal, mut vertical) = (Vec::new(), Vec::new());
+    for i in 0..n {
+        let ([x1, y1], [x2, y2]) = (poly[i], poly[(i + 1) % n]);
+        if y1 == y2 {
+            horizontal.push(Edge { start: i, at: y1, from: x1.min(x2), to: x1.max(x2) });
+        } else if x1 == x2 {
+            vertical.push(Edge { start: i, at: x1, from: y1.min(y2), to: y1.max(y2) });
+        } else {
+            return Err(tile_error(format!("Diagonal edge to {}", vertex((i + 1) % n)), i));
+        }
+    }
+
+    let adjacent = |a: usize, b: usize| (a + 1) % n == b || (b + 1) % n == a;
+    let crossing = |a: usize, b: usize| {
+        let (a, b) = (a.min(b), a.max(b));
+        tile_error(format!("Edge crosses the edge from {}", vertex(b)), a)
+    };
+
+    // collinear edges, which may only meet at the vertex between consecutive edges
+    for edges in [&mut horizontal, &mut vertical] {
+        edges.sort_by_key(|edge| (edge.at, edge.from));
+        for line in edges.chunk_by(|a, b| a.at == b.at) {
+            let mut furthest = line[0];
+            for &edge in &line[1..] {
+                if edge.from < furthest.to || (edge.from == furthest.to && !adjacent(edge.start, furthest.start)) {
+                    return Err(crossing(edge.start, furthest.start));
+                }
+                if edge.to > furthest.to { furthest = edge; }
+            }
+        }
+    }
+
+    // perpendicular edges, by sweeping across x: horizontal edges are active over `from..=to`,
+    // and each vertical edge is checked against the active edges in its span
+    #[derive(PartialEq, Eq, PartialOrd, Ord)]
+    enum Event { Start, Vertical, End }
+    let mut events: Vec<(i64, Event, usize)> = Vec::with_capacity(2 * horizontal.len() + vertical.len());
+    events.extend(horizontal.iter().enumerate().flat_map(|(h, edge)| [(edge.from, Event::Start, h), (edge.to, Event::End, h)]));
+    events.extend(vertical.iter().enumerate().map(|(v, edge)| (edge.at, Event::Vertical, v)));
+    events.sort_unstable();
+
+    let mut active: BTreeMap<i64, Vec<usize>> = BTreeMap::new();
+    for (_, event, k) in events {
+        match event {
+            Event::Start => active.entry(horizontal[k].at).or_default().push(horizontal[k].start),
+            Event::End => active.get_mut(&horizontal[k].at).unwrap().retain(|&start| start != horizontal[k].start),
+            Event::Vertical => {
+                let edge = vertical[k];
+                for starts in active.range(edge.from..=edge.to).map(|(_, starts)| starts) {
+                    if let Some(&start) = starts.iter().find(|&&start| !adjacent(start, edge.start)) {
+                        return Err(crossing(start, edge.start));
+                    }
+                }
+            },
+        }
+    }
+
+    Ok(if signed_area2(poly) > 0 { Winding::Clockwise } else { Winding::CounterClockwise })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_line(poly: &[Point<2>]) -> Option<usize> {
+        match validate(poly, 1) {
+            Err(Error::Parse { line, .. }) => line,
+            other => panic!("expected a parse error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn valid_loops() {
+        let square = [[0, 0], [4, 0], [4, 4], [0, 4]];
+        assert_eq!(validate(&square, 1).unwrap(), Winding::Clockwise);
+        let reversed: Vec<_> = square.iter().rev().copied().collect();
+        assert_eq!(validate(&reversed, 1).unwrap(), Winding::CounterClockwise);
+
+        // collinear vertex, and an L shape
+        assert!(validate(&[[0, 0], [2, 0], [4, 0], [4, 4], [0, 4]], 1).is_ok());
+        assert!(validate(&[[0, 0], [4, 0], [4, 2], [2, 2], [2, 4], [0, 4]], 1).is_ok());
+    }
+
+    #[test]
+    fn measures() {
+        let square = [[0, 0], [4, 0], [4, 4], [0, 4]];
+        assert_eq!((boundary_length(&square), tile_count(&square)), (16, 25));
+        // L shape, covering a 5x5 square but for a 2x2 corner
+        let l_shape = [[0, 0], [4, 0], [4, 2], [2, 2], [2, 4], [0, 4]];
+        assert_eq!((boundary_length(&l_shape), tile_count(&l_shape)), (16, 21));
+        let reversed: Vec<_> = l_shape.iter().rev().copied().collect();
+        assert_eq!(tile_count(&reversed), 21);
+    }
+
+    #[test]
+    fn invalid_loops() {
+        assert_eq!(error_line(&[[0, 0], [4, 0], [4, 4]]), Some(1));
+        assert_eq!(error_line(&[[0, 0], [4, 0], [4, 4], [0, 4], [4, 0]]), Some(5));
+        assert_eq!(error_line(&[[0, 0], [4, 0], [3, 4], [0, 4]]), Some(2));
+        // figure of eight, crossing in the middle
+        assert_eq!(error_line(&[[0, 0], [2, 0], [2, 4], [4, 4], [4, 2], [0, 2]]), Some(2));
+        // backtracking along a line
+        assert_eq!(error_line(&[[0, 0], [4, 0], [2, 0], [2, 4], [0, 4]]), Some(1));
+
+        let message = |poly: &[Point<2>]| validate(poly, 3).unwrap_err().to_string();
+        assert_eq!(message(&[[0, 0], [4, 0], [3, 4], [0, 4]]), "line 4: Vertex 1: Diagonal edge to vertex 2 on line 5: '4,0'");
+        assert_eq!(message(&[[0, 0], [4, 0], [4, 4], [0, 4], [4, 0]]), "line 7: Vertex 4: Duplicate of vertex 1 on line 4: '4,0'");
+    }
+}
@@ -0,0 +1,231 @@
+use std::{fs::File, io::{self, BufWriter, Write}, path::{Path, PathBuf}};
+
+use super::{point::Point, polygon::FillRule, Result};
+
+/// Longest side of raster images, in pixels
+pub const RASTER_SIZE: u32 = 1000;
+
+pub type Rgb = [u8; 3];
+
+const BACKGROUND: Rgb = [255, 255, 255];
+const INTERIOR: Rgb = [198, 219, 239];
+const OUTLINE: Rgb = [33, 66, 110];
+
+/// Image formats, chosen by file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Svg,
+    Ppm,
+    Png,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "svg" => Some(Format::Svg),
+            "ppm" => Some(Format::Ppm),
+            "png" => Some(Format::Png),
+            _ => None,
+        }
+    }
+}
+
+/// Command line parser for image paths with a supported extension
+pub fn parse_image_path(s: &str) -> std::result::Result<PathBuf, String> {
+    let path = PathBuf::from(s);
+    match Format::from_path(&path) {
+        Some(_) => Ok(path),
+        None => Err("Expected a .svg, .ppm or .png file".to_owned()),
+    }
+}
+
+/// Loops of tiles with highlighted rectangles, in tile coordinates. Each tile is a unit square,
+/// so loops run through tile centres and rectangles cover their corner tiles.
+pub struct Scene<'a> {
+    pub loops: Vec<&'a [Point<2>]>,
+    /// How the interior of the loops is filled
+    pub fill: FillRule,
+    /// Opposite corner tiles of each rectangle, and its outline colour
+    pub rects: Vec<([Point<2>; 2], Rgb)>,
+}
+
+impl Scene<'_> {
+    /// Write the scene to `path`, in the format given by its extension
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let format = Format::from_path(path)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Unsupported image format"))?;
+        let mut file = BufWriter::new(File::create(path)?);
+        match format {
+            Format::Svg => self.write_svg(&mut file)?,
+            Format::Ppm => {
+                let (width, height, pixels) = self.rasterise(RASTER_SIZE);
+                write!(file, "P6\n{width} {height}\n255\n")?;
+                file.write_all(&pixels)?;
+            },
+            Format::Png => {
+                let (width, height, pixels) = self.rasterise(RASTER_SIZE);
+                let mut encoder = png::Encoder::new(&mut file, width, height);
+                encoder.set_color(png::ColorType::Rgb);
+                encoder.set_depth(png::BitDepth::Eight);
+                encoder.write_header().map_err(io::Error::from)?
+                    .write_image_data(&pixels).map_err(io::Error::from)?;
+            },
+        }
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Top left and bottom right corners of the tiles in the scene
+    fn bounds(&self) -> ([f64; 2], [f64; 2]) {
+        let corners = self.loops.iter().flat_map(|poly| poly.iter()).chain(self.rects.iter().flat_map(|(corners, _)| corners));
+        let (mut min, mut max) = ([f64::INFINITY; 2], [f64::NEG_INFINITY; 2]);
+        for corner in corners {
+            for axis in 0..2 {
+                min[axis] = min[axis].min(corner[axis] as f64);
+                max[axis] = max[axis].max(corner[axis] as f64 + 1.0);
+            }
+        }
+        if min[0] > max[0] { ([0.0; 2], [1.0; 2]) } else { (min, max) }
+    }
+
+    fn write_svg(&self, w: &mut impl Write) -> io::Result<()> {
+        let (min, max) = self.bounds();
+        let margin = (max[0] - min[0]).max(max[1] - min[1]) * 0.02;
+        writeln!(
+            w, r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+            min[0] - margin, min[1] - margin, max[0] - min[0] + 2.0 * margin, max[1] - min[1] + 2.0 * margin,
+        )?;
+        let colour = |[r, g, b]: Rgb| format!("#{r:02x}{g:02x}{b:02x}");
+
+        let path: Vec<String> = self.loops.iter().map(|poly| {
+            let points: Vec<String> = poly.iter().map(|&[x, y]| format!("{},{}", x as f64 + 0.5, y as f64 + 0.5)).collect();
+            format!("M{}Z", points.join(" L"))
+        }).collect();
+        let fill_rule = match self.fill {
+            FillRule::EvenOdd => "evenodd",
+            FillRule::NonZero => "nonzero",
+        };
+        writeln!(
+            w, r#"  <path d="{}" fill="{}" fill-rule="{}" stroke="{}" stroke-width="1" vector-effect="non-scaling-stroke"/>"#,
+            path.join(" "), colour(INTERIOR), fill_rule, colour(OUTLINE),
+        )?;
+        for &([[x1, y1], [x2, y2]], rgb) in &self.rects {
+            writeln!(
+                w, r#"  <rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="{}" stroke-width="2" vector-effect="non-scaling-stroke"/>"#,
+                x1.min(x2), y1.min(y2), x1.abs_diff(x2) + 1, y1.abs_diff(y2) + 1, colour(rgb),
+            )?;
+        }
+        writeln!(w, "</svg>")
+    }
+
+    /// Rasterise to at most `size` pixels on the longest side, giving `(width, height, rgb pixels)`
+    fn rasterise(&self, size: u32) -> (u32, u32, Vec<u8>) {
+        let (min, max) = self.bounds();
+        let scale = size as f64 / (max[0] - min[0]).max(max[1] - min[1]);
+        let width = (((max[0] - min[0]) * scale).ceil() as u32).max(1);
+        let height = (((max[1] - min[1]) * scale).ceil() as u32).max(1);
+        let mut canvas = Canvas { width, height, pixels: vec![BACKGROUND; (width * height) as usize] };
+        let to_pixel = |v: f64, axis: usize| (v - min[axis]) * scale;
+
+        let edges = || self.loops.iter().flat_map(|poly| (0..poly.len()).map(|i| (poly[i], poly[(i + 1) % poly.len()])));
+
+        // fill pixel centres in each row by the winding number of the loops through tile
+        // centres, with upwards edges counting positive
+        let mut crossings = Vec::new();
+        for py in 0..height {
+            let y = min[1] + (py as f64 + 0.5) / scale;
+            crossings.clear();
+            for ([x1, y1], [_, y2]) in edges() {
+                let (lo, hi) = (y1.min(y2) as f64 + 0.5, y1.max(y2) as f64 + 0.5);
+                if lo <= y && y < hi {
+                    crossings.push((to_pixel(x1 as f64 + 0.5, 0), if y1 > y2 { 1 } else { -1 }));
+                }
+            }
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+            let mut winding = 0;
+            for (i, &(x, sign)) in crossings.iter().enumerate() {
+                winding += sign;
+                if let Some(&(next, _)) = crossings.get(i + 1) {
+                    if self.fill.inside(winding) {
+                        canvas.fill_row(py, x.round() as i64, next.round() as i64, INTERIOR);
+                    }
+                }
+            }
+        }
+
+        for ([x1, y1], [x2, y2]) in edges() {
+            let from = [to_pixel(x1 as f64 + 0.5, 0), to_pixel(y1 as f64 + 0.5, 1)];
+            let to = [to_pixel(x2 as f64 + 0.5, 0), to_pixel(y2 as f64 + 0.5, 1)];
+            canvas.line(from, to, OUTLINE);
+        }
+        for &([[x1, y1], [x2, y2]], rgb) in &self.rects {
+            let (left, right) = (to_pixel(x1.min(x2) as f64, 0), to_pixel(x1.max(x2) as f64 + 1.0, 0) - 1.0);
+            let (top, bottom) = (to_pixel(y1.min(y2) as f64, 1), to_pixel(y1.max(y2) as f64 + 1.0, 1) - 1.0);
+            for (from, to) in [
+                ([left, top], [right, top]), ([right, top], [right, bottom]),
+                ([right, bottom], [left, bottom]), ([left, bottom], [left, top]),
+            ] {
+                canvas.line(from, to, rgb);
+            }
+        }
+
+        (width, height, canvas.pixels.into_iter().flatten().collect())
+    }
+}
+
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<Rgb>,
+}
+
+impl Canvas {
+    fn set(&mut self, x: i64, y: i64, rgb: Rgb) {
+        if (0..self.width as i64).contains(&x) && (0..self.height as i64).contains(&y) {
+            self.pixels[(y * self.width as i64 + x) as usize] = rgb;
+        }
+    }
+
+    fn fill_row(&mut self, y: u32, from: i64, to: i64, rgb: Rgb) {
+        for x in from.max(0)..to.min(self.width as i64) {
+            self.set(x, y as i64, rgb);
+        }
+    }
+
+    /// Draw a line by stepping along its longer axis
+    fn line(&mut self, from: [f64; 2], to: [f64; 2], rgb: Rgb) {
+        let steps = (to[0] - from[0]).abs().max((to[1] - from[1]).abs()).ceil().max(1.0);
+        // avoid spending forever on lines far outside the canvas
+        let steps = steps.min(4.0 * (self.width + self.height) as f64) as i64;
+        for step in 0..=steps {
+            let t = step as f64 / steps as f64;
+            let (x, y) = (from[0] + (to[0] - from[0]) * t, from[1] + (to[1] - from[1]) * t);
+            self.set(x.floor() as i64, y.floor() as i64, rgb);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats() {
+        assert_eq!(Format::from_path(Path::new("out.svg")), Some(Format::Svg));
+        assert_eq!(Format::from_path(Path::new("dir.d/out.PNG")), Some(Format::Png));
+        assert_eq!(Format::from_path(Path::new("out.ppm")), Some(Format::Ppm));
+        assert_eq!(Format::from_path(Path::new("out.jpg")), None);
+        assert_eq!(Format::from_path(Path::new("out")), None);
+    }
+
+    #[test]
+    fn rasterise_square() {
+        let poly = [[0, 0], [9, 0], [9, 9], [0, 9]];
+        let scene = Scene { loops: vec![&poly], fill: FillRule::EvenOdd, rects: vec![] };
+        let (width, height, pixels) = scene.rasterise(10);
+        assert_eq!((width, height, pixels.len()), (10, 10, 300));
+        let pixel = |x: usize, y: usize| &pixels[3 * (y * 10 + x)..][..3];
+        assert_eq!(pixel(5, 5), INTERIOR);
+        assert_eq!(pixel(0, 5), OUTLINE);
+    }
+}
@@ -0,0 +1,170 @@
+use std::{fmt, io::BufRead, time::{Duration, Instant}};
+
+use clap::{ArgMatches, Command};
+use toml::Table;
+
+use super::Result;
+
+/// A solver for a single day. Day-specific options are parsed from the command line (`clap::Args`).
+pub trait Solution: clap::Args {
+    const DAY: u8;
+
+    type Input;
+    type Part1: Into<Answer>;
+    type Part2: Into<Answer>;
+
+    /// Fill in options not given on the command line from the input's parameters in `answers.toml`
+    fn configure(&mut self, _params: &Table) -> Result<()> {
+        Ok(())
+    }
+
+    fn parse<R: BufRead>(&self, input: R) -> Result<Self::Input>;
+
+    fn part1(&self, input: &Self::Input) -> Result<Self::Part1>;
+
+    fn part2(&self, input: &Self::Input) -> Result<Self::Part2>;
+
+    /// Parse `input` and solve both parts
+    fn solve<R: BufRead>(&self, input: R) -> Result<Answers> {
+        let input = self.parse(input)?;
+        Ok(Answers {
+            part1: self.part1(&input)?.into(),
+            part2: self.part2(&input)?.into(),
+        })
+    }
+}
+
+/// Answer to a single part. Days without a second part answer `()`, giving `Answer::None`.
+#[derive(Debug, Clone, Copy, Eq)]
+pub enum Answer {
+    Unsigned(u64),
+    Signed(i64),
+    None,
+}
+
+impl Answer {
+    /// Numeric value of the answer, if any
+    pub fn value(&self) -> Option<i128> {
+        match *self {
+            Answer::Unsigned(v) => Some(v.into()),
+            Answer::Signed(v) => Some(v.into()),
+            Answer::None => None,
+        }
+    }
+}
+
+impl PartialEq for Answer {
+    fn eq(&self, other: &Self) -> bool {
+        // compare by value, regardless of signedness
+        self.value() == other.value()
+    }
+}
+
+impl From<u64> for Answer {
+    fn from(value: u64) -> Self { Answer::Unsigned(value) }
+}
+
+impl From<u32> for Answer {
+    fn from(value: u32) -> Self { Answer::Unsigned(value.into()) }
+}
+
+impl From<usize> for Answer {
+    fn from(value: usize) -> Self { Answer::Unsigned(value as u64) }
+}
+
+impl From<i64> for Answer {
+    fn from(value: i64) -> Self { Answer::Signed(value) }
+}
+
+impl From<()> for Answer {
+    fn from(_: ()) -> Self { Answer::None }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Unsigned(v) => v.fmt(f),
+            Answer::Signed(v) => v.fmt(f),
+            Answer::None => "-".fmt(f),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Answers {
+    pub part1: Answer,
+    pub part2: Answer,
+}
+
+/// Answers from running a day, with the wall time of each stage
+#[derive(Debug)]
+pub struct Report {
+    pub parse_time: Duration,
+    pub part1: Result<Answer>,
+    pub part1_time: Duration,
+    pub part2: Result<Answer>,
+    pub part2_time: Duration,
+}
+
+impl Report {
+    /// Both answers, or the first failure
+    pub fn answers(self) -> Result<Answers> {
+        Ok(Answers { part1: self.part1?, part2: self.part2? })
+    }
+}
+
+type RunFn = fn(&ArgMatches, &Table, &mut dyn BufRead) -> Result<Report>;
+
+/// Type-erased registry entry for a [`Solution`]
+pub struct Day {
+    pub day: u8,
+    augment: fn(Command) -> Command,
+    run: RunFn,
+}
+
+impl Day {
+    pub const fn new<S: Solution>() -> Self {
+        Self {
+            day: S::DAY,
+            augment: S::augment_args,
+            run: run_solution::<S>,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        format!("day{}", self.day)
+    }
+
+    /// Subcommand accepting this day's options
+    pub fn command(&self) -> Command {
+        (self.augment)(Command::new(self.name()))
+    }
+
+    pub fn run(&self, matches: &ArgMatches, params: &Table, input: &mut dyn BufRead) -> Result<Answers> {
+        self.run_timed(matches, params, input)?.answers()
+    }
+
+    /// Run both parts, even if part 1 fails. Only parsing errors are returned directly.
+    pub fn run_timed(&self, matches: &ArgMatches, params: &Table, input: &mut dyn BufRead) -> Result<Report> {
+        (self.run)(matches, params, input)
+    }
+}
+
+fn run_solution<S: Solution>(matches: &ArgMatches, params: &Table, input: &mut dyn BufRead) -> Result<Report> {
+    let mut solution = S::from_arg_matches(matches)?;
+    solution.configure(params)?;
+
+    let start = Instant::now();
+    let input = solution.parse(input)?;
+    let parse_time = start.elapsed();
+
+    let start = Instant::now();
+    let part1 = solution.part1(&input).map(Into::into);
+    let part1_time = start.elapsed();
+
+    let start = Instant::now();
+    let part2 = solution.part2(&input).map(Into::into);
+    let part2_time = start.elapsed();
+
+    Ok(Report { parse_time, part1, part1_time, part2, part2_time })
+}
@@ -0,0 +1,47 @@
+//! Fixtures shared by unit tests
+
+use super::point::Point;
+
+/// Deterministic xorshift generator, so randomised tests see the same inputs on every run
+pub struct Rng(u64);
+
+impl Rng {
+    /// Generator starting from a non-zero `seed`
+    pub fn new(seed: u64) -> Self {
+        assert_ne!(seed, 0, "xorshift gets stuck at zero");
+        Self(seed)
+    }
+
+    /// Value in `0..range`
+    pub fn below(&mut self, range: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % range
+    }
+
+    /// Value in `0..range`, shifted down by `range / 2` to straddle zero
+    pub fn centred(&mut self, range: u64) -> i64 {
+        self.below(range) as i64 - (range / 2) as i64
+    }
+
+    /// `n` points with centred coordinates, so small ranges give plenty of duplicates
+    pub fn points<const N: usize>(&mut self, n: usize, range: u64) -> Vec<Point<N>> {
+        (0..n).map(|_| std::array::from_fn(|_| self.centred(range))).collect()
+    }
+}
+
+/// Every pair of indices below `n`, with the lower first
+pub fn pairs(n: usize) -> impl Iterator<Item = (usize, usize)> {
+    (0..n).flat_map(move |i| (i + 1..n).map(move |j| (i, j)))
+}
+
+/// Sum of each row over the columns of a 0/1 matrix, each taken `presses[j]` times, where column
+/// `j` has ones in rows `columns[j]`
+pub fn column_sums(columns: &[Vec<usize>], presses: &[u64], rows: usize) -> Vec<u64> {
+    let mut sums = vec![0; rows];
+    for (column, &n) in columns.iter().zip(presses) {
+        for &i in column { sums[i] += n; }
+    }
+    sums
+}
@@ -0,0 +1,146 @@
+//! Published answers for the `dayN_test.txt` example inputs
+
+use std::{fs::File, io::BufReader};
+
+use advent::{day1, day2, day3, day4, day5, day6, day7, day8, day9, day10, day11, day12};
+use advent::{polygon::FillRule, Answer, Answers, Error, Result, Solution};
+
+fn solve<S: Solution>(solution: S) -> Result<Answers> {
+    let file = File::open(advent::input_path(S::DAY, true))?;
+    solution.solve(BufReader::new(file))
+}
+
+fn answers(part1: impl Into<Answer>, part2: impl Into<Answer>) -> Answers {
+    Answers { part1: part1.into(), part2: part2.into() }
+}
+
+#[test]
+fn day1() {
+    assert_eq!(solve(day1::Day1).unwrap(), answers(1_u64, 4_u64));
+}
+
+#[test]
+fn day2() {
+    assert_eq!(solve(day2::Day2).unwrap(), answers(1227775554_u64, 4174379265_u64));
+}
+
+#[test]
+fn day3() {
+    assert_eq!(solve(day3::Day3).unwrap(), answers(357_u64, 3121910778619_u64));
+}
+
+#[test]
+fn day4() {
+    assert_eq!(solve(day4::Day4).unwrap(), answers(13_u64, 43_u64));
+}
+
+#[test]
+fn day5() {
+    assert_eq!(solve(day5::Day5).unwrap(), answers(3_u64, 14_u64));
+}
+
+#[test]
+fn day6() {
+    assert_eq!(solve(day6::Day6).unwrap(), answers(4277556_u64, 3263827_u64));
+}
+
+#[test]
+fn day7() {
+    assert_eq!(solve(day7::Day7).unwrap(), answers(21_u64, 40_u64));
+}
+
+#[test]
+fn day8() {
+    assert_eq!(solve(day8::Day8 { connections: Some(10), ..Default::default() }).unwrap(), answers(40_u64, 25272_u64));
+}
+
+#[test]
+fn day9() {
+    let expected = answers(50_u64, 24_u64);
+    assert_eq!(solve(day9::Day9::default()).unwrap(), expected);
+    assert_eq!(solve(day9::Day9 { compress: false, ..Default::default() }).unwrap(), expected);
+}
+
+#[test]
+fn day10() {
+    let expected = answers(7_u64, 33_u64);
+    for light_solver in [day10::LightSolver::Elimination, day10::LightSolver::Bfs] {
+        assert_eq!(solve(day10::Day10 { light_solver, ..Default::default() }).unwrap(), expected);
+    }
+}
+
+#[test]
+fn day11() {
+    assert_eq!(solve(day11::Day11).unwrap(), answers(5_u64, 0_u64));
+}
+
+#[test]
+fn day12() {
+    // the example's regions all need a search for an arrangement
+    assert_eq!(solve(day12::Day12).unwrap(), answers(2_u64, ()));
+}
+
+#[test]
+fn day11_part2() {
+    // part 2 has its own example, where part 1 has no paths from `you`
+    let input = "svr: aaa bbb\naaa: fft\nfft: ccc\nbbb: tty\ntty: ccc\nccc: ddd eee\nddd: hub\nhub: fff\neee: dac\ndac: fff\nfff: ggg hhh\nggg: out\nhhh: out\n";
+    let devices = day11::Day11.parse(input.as_bytes()).unwrap();
+    assert_eq!(day11::Day11.part2(&devices).unwrap(), 2);
+}
+
+#[test]
+fn day9_shifted() {
+    // moving the example across the axes doesn't change the areas
+    let example = std::fs::read_to_string(advent::input_path(9, true)).unwrap();
+    let tiles = day9::Day9::default().parse(example.as_bytes()).unwrap();
+    let shifted: String = tiles.points.iter().map(|[x, y]| format!("{},{}\n", x - 7, y - 1)).collect();
+
+    for compress in [true, false] {
+        assert_eq!(day9::Day9 { compress, ..Default::default() }.solve(shifted.as_bytes()).unwrap(), answers(50_u64, 24_u64));
+    }
+}
+
+#[test]
+fn day9_reversed() {
+    // the same loop the other way round
+    let example = std::fs::read_to_string(advent::input_path(9, true)).unwrap();
+    let reversed: String = example.lines().rev().map(|line| format!("{line}\n")).collect();
+    assert_eq!(day9::Day9::default().solve(reversed.as_bytes()).unwrap(), answers(50_u64, 24_u64));
+}
+
+#[test]
+fn day9_holes() {
+    // a square with a square hole, which only the even-odd rule leaves out when both run the same way
+    let hole = "0,0\n10,0\n10,10\n0,10\n\n3,3\n7,3\n7,7\n3,7\n";
+    let reversed_hole = "0,0\n10,0\n10,10\n0,10\n\n3,7\n7,7\n7,3\n3,3\n";
+    for (input, fill, part2) in [
+        (hole, FillRule::EvenOdd, 32_u64), (hole, FillRule::NonZero, 121),
+        (reversed_hole, FillRule::EvenOdd, 32), (reversed_hole, FillRule::NonZero, 32),
+    ] {
+        for compress in [true, false] {
+            let solution = day9::Day9 { compress, fill, ..Default::default() };
+            assert_eq!(solution.solve(input.as_bytes()).unwrap(), answers(121_u64, part2), "{fill:?}");
+        }
+    }
+}
+
+#[test]
+fn day9_islands() {
+    // the gap between the islands is narrower than a tile on the compressed grid
+    let input = "0,0\n2,0\n2,2\n0,2\n\n5,0\n7,0\n7,2\n5,2\n";
+    for compress in [true, false] {
+        let solution = day9::Day9 { compress, ..Default::default() };
+        assert_eq!(solution.solve(input.as_bytes()).unwrap(), answers(24_u64, 9_u64));
+    }
+}
+
+#[test]
+fn day10_skip_infeasible() {
+    // the example with a machine whose lights and voltages can't both be reached
+    let example = std::fs::read_to_string(advent::input_path(10, true)).unwrap();
+    let input: String = example.lines().chain(["[#.] (0,1) {1,2}"]).map(|line| format!("{line}\n")).collect();
+    let solution = day10::Day10 { skip_infeasible: true, ..Default::default() };
+    assert_eq!(solution.solve(input.as_bytes()).unwrap(), answers(7_u64, 33_u64));
+    let solution = day10::Day10 { skip_infeasible: false, ..solution };
+    assert!(matches!(solution.solve(input.as_bytes()), Err(Error::Unsolvable(_))));
+}